@@ -1,3 +1,6 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::io::BufRead;
+
 fn main() {
     let xs: Vec<u32> = include_str!("input.txt")
         .lines()
@@ -9,6 +12,19 @@ fn main() {
     println!();
     println!("[imperative] Day01 part a = {}", part_a_imperative(&xs));
     println!("[imperative] Day01 part b = {}", part_b_imperative(&xs));
+    println!();
+    for k in [1, 3] {
+        let counts = sweep_reader(include_str!("input.txt").as_bytes(), k).unwrap();
+        println!("[streaming]  Day01 window {} = {:?}", k, counts);
+    }
+    println!();
+
+    let profile = DepthProfile::new(&xs, 10, 200).unwrap();
+    if std::env::args().any(|arg| arg == "--csv") {
+        print!("{}", profile.csv());
+    } else {
+        print!("{}", profile.table());
+    }
 }
 
 // functional
@@ -62,10 +78,197 @@ fn part_b_imperative(xs: &[u32]) -> u32 {
     part_a_imperative(&ys)
 }
 
+// streaming
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct SweepCounts {
+    increases: usize,
+    decreases: usize,
+    equal: usize,
+}
+
+// comparing sliding window sums of size k reduces to xs[i + k] vs xs[i],
+// the k - 1 shared middle values cancel out
+fn sweep<I: IntoIterator<Item=u32>>(xs: I, k: usize) -> SweepCounts {
+    assert!(k > 0);
+    let mut counts = SweepCounts::default();
+    let mut window: VecDeque<u32> = VecDeque::with_capacity(k);
+    for x in xs {
+        if window.len() == k {
+            let old = window.pop_front().unwrap();
+            match x.cmp(&old) {
+                std::cmp::Ordering::Greater => counts.increases += 1,
+                std::cmp::Ordering::Less => counts.decreases += 1,
+                std::cmp::Ordering::Equal => counts.equal += 1,
+            }
+        }
+        window.push_back(x);
+    }
+    counts
+}
+
+fn sweep_reader<R: BufRead>(reader: R, k: usize) -> Result<SweepCounts, String> {
+    if k == 0 {
+        return Err("window size must be at least 1".to_string());
+    }
+
+    let mut error = None;
+    let depths = reader
+        .lines()
+        .enumerate()
+        .map_while(|(i, line)| {
+            let parsed = line
+                .map_err(|e| format!("line {}: {}", i + 1, e))
+                .and_then(|s| s.trim().parse::<u32>().map_err(|e| format!("line {}: {:?}, {}", i + 1, s, e)));
+            match parsed {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    error = Some(e);
+                    None
+                }
+            }
+        });
+    let counts = sweep(depths, k);
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(counts),
+    }
+}
+
+// profile
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Run {
+    start: usize,
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Jump {
+    index: usize,
+    delta: i64,
+}
+
+#[derive(Debug)]
+struct DepthProfile {
+    samples: usize,
+    increases: u32,
+    window_increases: u32,
+    longest_increasing: Run,
+    longest_decreasing: Run,
+    largest_jump: Jump,
+    bucket_width: i64,
+    histogram: BTreeMap<i64, usize>,
+    window_size: usize,
+    window_averages: Vec<f64>,
+}
+
+impl DepthProfile {
+    fn new(xs: &[u32], bucket_width: i64, window_size: usize) -> Result<DepthProfile, String> {
+        if xs.len() < 3 {
+            return Err(format!("need at least 3 samples, got {}", xs.len()));
+        }
+        if bucket_width < 1 || window_size < 1 {
+            return Err("bucket width and window size must be at least 1".to_string());
+        }
+
+        let deltas: Vec<i64> = xs.windows(2).map(|w| w[1] as i64 - w[0] as i64).collect();
+
+        let mut histogram = BTreeMap::new();
+        for delta in &deltas {
+            *histogram.entry(delta.div_euclid(bucket_width) * bucket_width).or_insert(0) += 1;
+        }
+
+        let (index, delta) = deltas.iter()
+            .enumerate()
+            .fold((0, 0i64), |(i_max, d_max), (i, d)| if d.abs() > d_max.abs() { (i, *d) } else { (i_max, d_max) });
+
+        let window_averages = xs
+            .chunks(window_size)
+            .map(|chunk| chunk.iter().map(|x| *x as f64).sum::<f64>() / chunk.len() as f64)
+            .collect();
+
+        Ok(DepthProfile {
+            samples: xs.len(),
+            increases: part_a_imperative(xs),
+            window_increases: part_b_imperative(xs),
+            longest_increasing: longest_run(&deltas, |d| d > 0),
+            longest_decreasing: longest_run(&deltas, |d| d < 0),
+            largest_jump: Jump { index, delta },
+            bucket_width,
+            histogram,
+            window_size,
+            window_averages,
+        })
+    }
+
+    fn bucket_label(&self, lo: i64) -> String {
+        format!("{}..={}", lo, lo + self.bucket_width - 1)
+    }
+
+    fn table(&self) -> String {
+        let mut s = String::new();
+        s += &format!("{:<28}{:>12}\n", "samples", self.samples);
+        s += &format!("{:<28}{:>12}\n", "increases", self.increases);
+        s += &format!("{:<28}{:>12}\n", "window increases", self.window_increases);
+        s += &format!("{:<28}{:>12}  (start {})\n", "longest increasing run", self.longest_increasing.len, self.longest_increasing.start);
+        s += &format!("{:<28}{:>12}  (start {})\n", "longest decreasing run", self.longest_decreasing.len, self.longest_decreasing.start);
+        s += &format!("{:<28}{:>12}  (index {})\n", "largest jump", self.largest_jump.delta, self.largest_jump.index);
+        s += "\ndelta histogram\n";
+        for (lo, count) in &self.histogram {
+            s += &format!("{:>16}  {:>8}\n", self.bucket_label(*lo), count);
+        }
+        s += &format!("\naverages per {} samples\n", self.window_size);
+        for (i, avg) in self.window_averages.iter().enumerate() {
+            s += &format!("{:>16}  {:>10.2}\n", i * self.window_size, avg);
+        }
+        s
+    }
+
+    fn csv(&self) -> String {
+        let mut s = "section,key,value\n".to_string();
+        s += &format!("summary,samples,{}\n", self.samples);
+        s += &format!("summary,increases,{}\n", self.increases);
+        s += &format!("summary,window_increases,{}\n", self.window_increases);
+        s += &format!("run,longest_increasing_len,{}\n", self.longest_increasing.len);
+        s += &format!("run,longest_increasing_start,{}\n", self.longest_increasing.start);
+        s += &format!("run,longest_decreasing_len,{}\n", self.longest_decreasing.len);
+        s += &format!("run,longest_decreasing_start,{}\n", self.longest_decreasing.start);
+        s += &format!("jump,largest_delta,{}\n", self.largest_jump.delta);
+        s += &format!("jump,largest_index,{}\n", self.largest_jump.index);
+        for (lo, count) in &self.histogram {
+            s += &format!("histogram,{},{}\n", self.bucket_label(*lo), count);
+        }
+        for (i, avg) in self.window_averages.iter().enumerate() {
+            s += &format!("window_average,{},{:.2}\n", i * self.window_size, avg);
+        }
+        s
+    }
+}
+
+// longest run of samples whose consecutive deltas all satisfy pred, first one wins on ties
+fn longest_run(deltas: &[i64], pred: fn(i64) -> bool) -> Run {
+    let mut best = Run::default();
+    let mut current = Run::default();
+    for (i, delta) in deltas.iter().enumerate() {
+        if pred(*delta) {
+            if current.len == 0 {
+                current = Run { start: i, len: 1 };
+            }
+            current.len += 1;
+            if current.len > best.len {
+                best = current;
+            }
+        } else {
+            current = Run::default();
+        }
+    }
+    best
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::{part_a_imperative, part_a_functional, part_b_imperative, part_b_functional};
+    use crate::{part_a_imperative, part_a_functional, part_b_imperative, part_b_functional, sweep, sweep_reader, SweepCounts,
+                DepthProfile, Jump, Run};
 
     static TS: [u32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
 
@@ -81,4 +284,37 @@ mod tests {
         assert_eq!(5, part_b_imperative(&TS));
         assert_eq!(5, part_b_functional(&TS));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_sweep() {
+        assert_eq!(SweepCounts { increases: 7, decreases: 2, equal: 0 }, sweep(TS, 1));
+        assert_eq!(SweepCounts { increases: 5, decreases: 1, equal: 1 }, sweep(TS, 3));
+        assert_eq!(SweepCounts::default(), sweep(TS, 10));
+
+        let data = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+        assert_eq!(Ok(sweep(TS, 3)), sweep_reader(data.as_bytes(), 3));
+        assert!(sweep_reader(data.as_bytes(), 0).is_err());
+        assert!(sweep_reader("199\n2x0\n".as_bytes(), 1).is_err());
+    }
+
+    #[test]
+    fn test_depth_profile() {
+        let profile = DepthProfile::new(&TS, 10, 4).unwrap();
+        assert_eq!(7, profile.increases);
+        assert_eq!(5, profile.window_increases);
+        assert_eq!(Run { start: 0, len: 4 }, profile.longest_increasing);
+        assert_eq!(Run { start: 3, len: 2 }, profile.longest_decreasing);
+        assert_eq!(Jump { index: 5, delta: 33 }, profile.largest_jump);
+        assert_eq!(vec![(-10, 2), (0, 5), (20, 1), (30, 1)],
+                   profile.histogram.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>());
+        assert_eq!(vec![204.25, 229.0, 261.5], profile.window_averages);
+
+        let csv = profile.csv();
+        assert!(csv.starts_with("section,key,value\n"));
+        assert!(csv.contains("histogram,-10..=-1,2\n"));
+        assert!(csv.contains("window_average,8,261.50\n"));
+
+        assert!(DepthProfile::new(&TS[..2], 10, 4).is_err());
+        assert!(DepthProfile::new(&TS, 0, 4).is_err());
+    }
+}
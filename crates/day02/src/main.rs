@@ -1,80 +1,323 @@
+use std::collections::HashMap;
+
 fn main() {
-    let xs: Vec<(&str, i64)> = include_str!("input.txt")
-        .lines()
-        .map(|s| {
-            let vs: Vec<&str> = s.split_whitespace().collect();
-            let direction = *vs.get(0).unwrap();
-            let v = vs.get(1).unwrap().parse::<i64>().unwrap();
-            (direction, v)
-        })
-        .collect();
+    let instructions = InstructionSet::standard();
+    let xs = instructions.parse_course(include_str!("input.txt")).unwrap();
+
+    println!("Day02 part a = {}", part_a(&xs).unwrap()); // 1990000
+    println!("Day02 part b = {}", part_b(&xs).unwrap()); // 1975421260
+
+    // optional custom course file, run with the extended instruction set
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(path) = args.iter().find(|arg| !arg.starts_with("--")) {
+        let course = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| InstructionSet::extended().parse_course(&data));
+        match course {
+            Ok(ys) => report(path, &ys, &args),
+            Err(e) => println!("{}: {}", path, e),
+        }
+    } else {
+        report("input.txt", &xs, &args);
+    }
+}
+
+fn report(label: &str, xs: &[Command], args: &[String]) {
+    let models: [&dyn SubmarineModel; 2] = [&SimpleModel, &AimModel];
+    let trajectories = match models.iter().map(|model| model.run(xs)).collect::<Result<Vec<Trajectory>, String>>() {
+        Ok(trajectories) => trajectories,
+        Err(e) => {
+            println!("{}: {}", label, e);
+            return;
+        }
+    };
+
+    if args.iter().any(|arg| arg == "--csv") {
+        print!("{}", trajectory_csv(&trajectories));
+    } else if args.iter().any(|arg| arg == "--svg") {
+        print!("{}", depth_plot(&trajectories, 800, 400));
+    } else {
+        for trajectory in &trajectories {
+            println!("{} {} model = {}", label, trajectory.model, trajectory.answer());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Forward(i64),
+    Down(i64),
+    Up(i64),
+    Back(i64),
+    Surface,
+    ResetAim,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Verb {
+    Magnitude(fn(i64) -> Command),
+    Bare(Command),
+}
 
-    println!("Day02 part a = {}", part_a(&xs)); // 1990000
-    println!("Day02 part b = {}", part_b(&xs)); // 1975421260
+#[derive(Debug, Clone)]
+struct InstructionSet {
+    verbs: HashMap<String, Verb>,
 }
 
-fn process_input(xs: &[(&str, i64)]) -> Vec<(i64, i64)> {
-    // Vec<(horizontal, vertical)>
-    xs.iter()
-        .map(|(s, v)| {
-            match *s {
-                "forward" => (*v, 0),
-                "down" => (0, *v),
-                "up" => (0, *v * -1),
-                _ => (0, 0),
+impl InstructionSet {
+    fn standard() -> InstructionSet {
+        let mut instructions = InstructionSet { verbs: HashMap::new() };
+        instructions.register("forward", Verb::Magnitude(Command::Forward));
+        instructions.register("down", Verb::Magnitude(Command::Down));
+        instructions.register("up", Verb::Magnitude(Command::Up));
+        instructions
+    }
+
+    fn extended() -> InstructionSet {
+        let mut instructions = InstructionSet::standard();
+        instructions.register("back", Verb::Magnitude(Command::Back));
+        instructions.register("surface", Verb::Bare(Command::Surface));
+        instructions.register("reset-aim", Verb::Bare(Command::ResetAim));
+        instructions
+    }
+
+    fn register(&mut self, name: &str, verb: Verb) {
+        self.verbs.insert(name.to_string(), verb);
+    }
+
+    fn parse(&self, s: &str) -> Result<Command, String> {
+        let vs: Vec<&str> = s.split_whitespace().collect();
+        let name = *vs.first().ok_or("empty command")?;
+        let verb = self.verbs.get(name).ok_or(format!("unknown command {:?}", name))?;
+        match (verb, &vs[1..]) {
+            (Verb::Bare(command), []) => Ok(*command),
+            (Verb::Bare(_), _) => Err(format!("{:?} takes no magnitude", name)),
+            (Verb::Magnitude(_), []) => Err(format!("{:?} is missing a magnitude", name)),
+            (Verb::Magnitude(f), [v]) => {
+                let v: i64 = v.parse().map_err(|_| format!("{:?} has an invalid magnitude {:?}", name, v))?;
+                if v < 0 {
+                    return Err(format!("{:?} has a negative magnitude {}", name, v));
+                }
+                Ok(f(v))
             }
-        })
-        .collect()
+            (Verb::Magnitude(_), _) => Err(format!("{:?} takes a single magnitude", name)),
+        }
+    }
+
+    fn parse_course(&self, data: &str) -> Result<Vec<Command>, String> {
+        data.lines()
+            .enumerate()
+            .filter(|(_, s)| !s.trim().is_empty())
+            .map(|(i, s)| self.parse(s).map_err(|e| format!("line {}: {}", i + 1, e)))
+            .collect()
+    }
 }
 
-fn part_a(xs: &[(&str, i64)]) -> i64 {
-    let (horizontal, vertical) = process_input(xs)
-        .iter()
-        .fold((0, 0), |(acc_x, acc_y), (x, y)| (acc_x + x, acc_y + y));
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct SubState {
+    position: i64,
+    depth: i64,
+    aim: i64,
+}
+
+trait SubmarineModel {
+    fn name(&self) -> &'static str;
 
-    horizontal * vertical
+    // None when the state no longer fits in an i64
+    fn step(&self, state: SubState, command: &Command) -> Option<SubState>;
+
+    fn run(&self, xs: &[Command]) -> Result<Trajectory, String> {
+        let mut states = vec![SubState::default()];
+        for (i, command) in xs.iter().enumerate() {
+            let state = self.step(*states.last().unwrap(), command)
+                .ok_or(format!("command {} {:?} overflows the {} model", i + 1, command, self.name()))?;
+            states.push(state);
+        }
+        Ok(Trajectory { model: self.name(), states })
+    }
 }
 
-fn part_b(xs: &[(&str, i64)]) -> i64 {
-    // Vec<(horizontal, aim)>, aim = cumulative vertical
-    let vs: Vec<(i64, i64)> = process_input(xs)
+// down and up change depth directly
+struct SimpleModel;
+
+impl SubmarineModel for SimpleModel {
+    fn name(&self) -> &'static str { "simple" }
+
+    fn step(&self, s: SubState, command: &Command) -> Option<SubState> {
+        match command {
+            Command::Forward(v) => Some(SubState { position: s.position.checked_add(*v)?, ..s }),
+            Command::Back(v) => Some(SubState { position: s.position.checked_sub(*v)?, ..s }),
+            Command::Down(v) => Some(SubState { depth: s.depth.checked_add(*v)?, ..s }),
+            Command::Up(v) => Some(SubState { depth: s.depth.checked_sub(*v)?, ..s }),
+            Command::Surface => Some(SubState { depth: 0, ..s }),
+            Command::ResetAim => Some(s),
+        }
+    }
+}
+
+// down and up change aim, moving changes depth by aim * distance
+struct AimModel;
+
+impl SubmarineModel for AimModel {
+    fn name(&self) -> &'static str { "aim" }
+
+    fn step(&self, s: SubState, command: &Command) -> Option<SubState> {
+        match command {
+            Command::Forward(v) => Some(SubState {
+                position: s.position.checked_add(*v)?,
+                depth: s.depth.checked_add(s.aim.checked_mul(*v)?)?,
+                ..s
+            }),
+            Command::Back(v) => Some(SubState {
+                position: s.position.checked_sub(*v)?,
+                depth: s.depth.checked_sub(s.aim.checked_mul(*v)?)?,
+                ..s
+            }),
+            Command::Down(v) => Some(SubState { aim: s.aim.checked_add(*v)?, ..s }),
+            Command::Up(v) => Some(SubState { aim: s.aim.checked_sub(*v)?, ..s }),
+            Command::Surface => Some(SubState { depth: 0, ..s }),
+            Command::ResetAim => Some(SubState { aim: 0, ..s }),
+        }
+    }
+}
+
+// states[0] is the starting state, states[i] is the state after command i
+#[derive(Debug, Clone)]
+struct Trajectory {
+    model: &'static str,
+    states: Vec<SubState>,
+}
+
+impl Trajectory {
+    // position * depth, which can outgrow i64 even when both fit
+    fn answer(&self) -> i128 {
+        let last = self.states.last().unwrap();
+        last.position as i128 * last.depth as i128
+    }
+}
+
+fn trajectory_csv(trajectories: &[Trajectory]) -> String {
+    trajectories
         .iter()
-        .scan((0i64, 0i64), |acc, (hor, aim)| {
-            acc.0 = *hor;
-            acc.1 += *aim;
-            Some(*acc)
+        .flat_map(|t| t.states.iter().enumerate().map(move |(i, s)| (t.model, i, s)))
+        .fold("model,step,position,depth,aim\n".to_string(), |acc, (model, i, s)| {
+            acc + &format!("{},{},{},{},{}\n", model, i, s.position, s.depth, s.aim)
         })
-        .collect();
+}
 
-    let (horizontal, depth) = vs
-        .iter()
-        .fold((0, 0), |(acc_x, acc_y), (x, y)| (acc_x + x, acc_y + (y * x)));
+static PLOT_COLORS: [&str; 4] = ["steelblue", "darkorange", "seagreen", "crimson"];
+
+// position on the x axis, depth increasing downwards, one polyline per trajectory
+fn depth_plot(trajectories: &[Trajectory], width: u32, height: u32) -> String {
+    let states = trajectories.iter().flat_map(|t| t.states.iter());
+    let (min_x, max_x, min_y, max_y) = states.fold((0, 0, 0, 0), |(x0, x1, y0, y1), s| {
+        (x0.min(s.position), x1.max(s.position), y0.min(s.depth), y1.max(s.depth))
+    });
+    let scale_x = width as f64 / (max_x - min_x).max(1) as f64;
+    let scale_y = height as f64 / (max_y - min_y).max(1) as f64;
 
-    horizontal * depth
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"-10 -10 {} {}\">\n",
+                          width + 20, height + 20, width + 20, height + 20);
+    for (i, trajectory) in trajectories.iter().enumerate() {
+        let points: Vec<String> = trajectory.states
+            .iter()
+            .map(|s| format!("{:.1},{:.1}", (s.position - min_x) as f64 * scale_x, (s.depth - min_y) as f64 * scale_y))
+            .collect();
+        let color = PLOT_COLORS[i % PLOT_COLORS.len()];
+        svg += &format!("  <polyline fill=\"none\" stroke=\"{}\" points=\"{}\"/>\n", color, points.join(" "));
+        svg += &format!("  <text x=\"0\" y=\"{}\" fill=\"{}\" font-size=\"12\">{}</text>\n", 12 * (i + 1), color, trajectory.model);
+    }
+    svg + "</svg>\n"
 }
 
+fn part_a(xs: &[Command]) -> Result<i128, String> {
+    SimpleModel.run(xs).map(|trajectory| trajectory.answer())
+}
+
+fn part_b(xs: &[Command]) -> Result<i128, String> {
+    AimModel.run(xs).map(|trajectory| trajectory.answer())
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::{part_a, part_b};
-
-    static TS: [(&str, i64); 6] = [
-        ("forward", 5),
-        ("down", 5),
-        ("forward", 8),
-        ("up", 3),
-        ("down", 8),
-        ("forward", 2),
-    ];
+    use crate::{part_a, part_b, depth_plot, trajectory_csv, AimModel, Command, InstructionSet, SimpleModel, SubState, SubmarineModel, Verb};
 
+    static TS: &str = "forward 5\ndown 5\nforward 8\nup 3\ndown 8\nforward 2\n";
 
     #[test]
     fn test_part_a() {
-        assert_eq!(150, part_a(&TS));
+        let xs = InstructionSet::standard().parse_course(TS).unwrap();
+        assert_eq!(Ok(150), part_a(&xs));
     }
 
     #[test]
     fn test_part_b() {
-        assert_eq!(900, part_b(&TS));
+        let xs = InstructionSet::standard().parse_course(TS).unwrap();
+        assert_eq!(Ok(900), part_b(&xs));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let instructions = InstructionSet::standard();
+        assert_eq!(Ok(Command::Forward(5)), instructions.parse("forward 5"));
+        assert!(instructions.parse("fowrard 5").is_err());
+        assert!(instructions.parse("down").is_err());
+        assert!(instructions.parse("down -3").is_err());
+        assert!(instructions.parse("down x").is_err());
+        assert!(instructions.parse("down 3 4").is_err());
+        assert!(instructions.parse("back 3").is_err());
+
+        let err = instructions.parse_course("forward 5\nup\n").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{}", err);
+    }
+
+    #[test]
+    fn test_extended_instructions() {
+        let mut instructions = InstructionSet::extended();
+        instructions.register("reverse", Verb::Magnitude(Command::Back));
+        assert!(instructions.parse("surface 1").is_err());
+
+        let xs = instructions.parse_course("forward 10\ndown 4\nforward 5\nreverse 3\nsurface\ndown 2\nreset-aim\nforward 1\n").unwrap();
+        assert_eq!(Ok(26), part_a(&xs));
+        assert_eq!(Ok(0), part_b(&xs));
+    }
+
+    #[test]
+    fn test_trajectory() {
+        let xs = InstructionSet::standard().parse_course(TS).unwrap();
+        let simple = SimpleModel.run(&xs).unwrap();
+        let aim = AimModel.run(&xs).unwrap();
+        assert_eq!(7, simple.states.len());
+        assert_eq!(SubState { position: 13, depth: 5, aim: 0 }, simple.states[3]);
+        assert_eq!(SubState { position: 13, depth: 40, aim: 5 }, aim.states[3]);
+        assert_eq!(SubState { position: 15, depth: 60, aim: 10 }, aim.states[6]);
+
+        let csv = trajectory_csv(&[simple.clone(), aim.clone()]);
+        assert_eq!(15, csv.lines().count());
+        assert!(csv.contains("simple,6,15,10,0\n"));
+        assert!(csv.ends_with("aim,6,15,60,10\n"));
+
+        let svg = depth_plot(&[simple, aim], 100, 50);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(2, svg.matches("<polyline").count());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let instructions = InstructionSet::standard();
+        let big = i64::MAX;
+
+        // aim * distance overflows before depth does
+        let xs = instructions.parse_course(&format!("down {}\nforward 2\n", big)).unwrap();
+        assert_eq!(Ok(2 * big as i128), part_a(&xs));
+        assert_eq!(Err("command 2 Forward(2) overflows the aim model".to_string()), part_b(&xs));
+
+        // each step fits, their sum does not
+        let xs = instructions.parse_course(&format!("forward {}\nforward 1\n", big)).unwrap();
+        assert_eq!(Err("command 2 Forward(1) overflows the simple model".to_string()), part_a(&xs));
+
+        // the answer itself outgrows i64
+        let xs = instructions.parse_course(&format!("forward {}\ndown {}\n", big, big)).unwrap();
+        assert_eq!(Ok(big as i128 * big as i128), part_a(&xs));
     }
 }
@@ -1,81 +1,284 @@
+use std::fmt;
+
 use utils::ones_bit_count;
 
 fn main() {
-    let xs: Vec<u16> = include_str!("input.txt")
-        .lines()
-        .map(|s| u16::from_str_radix(s, 2).unwrap())
-        .collect();
+    let report = Report::new(include_str!("input.txt")).unwrap();
+
+    println!("Day03 part a = {}", part_a(&report)); // 1131506
+    match part_b(&report) {
+        Some(rating) => println!("Day03 part b = {}", rating), // 7863147
+        None => println!("Day03 part b = no single survivor"),
+    }
 
-    println!("Day03 part a = {}", part_a(&xs, 12)); // 1131506
-    println!("Day03 part b = {}", part_b(&xs, 12)); // 7863147
+    // --report [--oxygen most:1] [--co2 least:0]
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--report") {
+        let policy_arg = |flag: &str, default: Policy| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|i| args.get(i + 1))
+                .map_or(Ok(default), |s| Policy::parse(s))
+        };
+        match (policy_arg("--oxygen", Policy::OXYGEN), policy_arg("--co2", Policy::CO2)) {
+            (Ok(oxygen), Ok(co2)) => print!("{}", report.diagnostics(oxygen, co2)),
+            (Err(e), _) | (_, Err(e)) => println!("{}", e),
+        }
+    }
 }
 
-fn part_a(xs: &Vec<u16>, str_len: usize) -> usize {
-    let len = xs.len();
-    let gamma_str = (0..str_len).rev()
-        .fold("".to_string(), |acc, k| {
-            let ones = ones_bit_count(xs, k);
-            acc + dominate_bit(ones, len - ones)
-        });
-    let gamma = usize::from_str_radix(&*gamma_str, 2).unwrap();
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Criteria { MostCommon, LeastCommon }
 
-    let epsilon_str: String = gamma_str.chars()
-        .map(|c| if c == '1' { '0' } else { '1' })
-        .collect();
-    let epsilon = usize::from_str_radix(&*epsilon_str, 2).unwrap();
+// which bit value is kept when ones and zeros are equally common
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TieBreak { One, Zero }
 
-    // println!("gamma_str:   {}, gamma:   {}", gamma_str, gamma);
-    // println!("epsilon_str: {}, epsilon: {}", epsilon_str, epsilon);
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Policy {
+    criteria: Criteria,
+    tie: TieBreak,
+}
 
-    gamma * epsilon
+impl Policy {
+    const OXYGEN: Policy = Policy { criteria: Criteria::MostCommon, tie: TieBreak::One };
+    const CO2: Policy = Policy { criteria: Criteria::LeastCommon, tie: TieBreak::Zero };
+
+    // "most:1", "most:0", "least:1" or "least:0"
+    fn parse(s: &str) -> Result<Policy, String> {
+        let criteria = match s.split(':').next() {
+            Some("most") => Criteria::MostCommon,
+            Some("least") => Criteria::LeastCommon,
+            _ => return Err(format!("unknown policy {:?}, expected most:<tie> or least:<tie>", s)),
+        };
+        let tie = match s.split(':').nth(1) {
+            Some("1") => TieBreak::One,
+            Some("0") => TieBreak::Zero,
+            _ => return Err(format!("unknown tie break in {:?}, expected 1 or 0", s)),
+        };
+        Ok(Policy { criteria, tie })
+    }
+
+    fn select(&self, ones: usize, zeros: usize) -> u128 {
+        if ones == zeros {
+            return match self.tie { TieBreak::One => 1, TieBreak::Zero => 0 };
+        }
+        match (self.criteria, ones > zeros) {
+            (Criteria::MostCommon, true) | (Criteria::LeastCommon, false) => 1,
+            _ => 0,
+        }
+    }
 }
 
-fn part_b(xs: &Vec<u16>, str_len: usize) -> usize {
-    let oxygen = filter(xs, str_len, true).unwrap() as usize;
-    let co2 = filter(xs, str_len, false).unwrap() as usize;
+// diagnostic report of equal length binary strings, up to 128 bits wide
+#[derive(Debug, Clone, PartialEq)]
+struct Report {
+    xs: Vec<u128>,
+    width: usize,
+}
+
+impl Report {
+    fn new(data: &str) -> Result<Report, String> {
+        let lines: Vec<&str> = data.lines().map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        let width = lines.first().ok_or("empty report")?.len();
+        if width > 128 {
+            return Err(format!("report is {} bits wide, at most 128 are supported", width));
+        }
+
+        let xs = lines
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                if s.len() != width {
+                    return Err(format!("line {}: expected {} bits, found {}", i + 1, width, s.len()));
+                }
+                // from_str_radix would also take a leading sign
+                if s.chars().any(|c| c != '0' && c != '1') {
+                    return Err(format!("line {}: {:?} is not binary", i + 1, s));
+                }
+                u128::from_str_radix(s, 2).map_err(|_| format!("line {}: {:?} is not binary", i + 1, s))
+            })
+            .collect::<Result<Vec<u128>, String>>()?;
+
+        Ok(Report { xs, width })
+    }
+
+    fn sorted(&self) -> Vec<u128> {
+        let mut sorted = self.xs.clone();
+        sorted.sort_unstable();
+        sorted
+    }
 
-    // println!("oxygen generator rating = {:>6}", oxygen);
-    // println!("CO2 scrubber rating     = {:>6}", co2);
+    // one bit per position, most significant first, chosen by policy from the bit counts
+    fn rate(&self, policy: Policy) -> u128 {
+        let len = self.xs.len();
+        (0..self.width).rev()
+            .fold(0u128, |acc, k| {
+                let ones = ones_bit_count(&self.xs, k);
+                (acc << 1) | policy.select(ones, len - ones)
+            })
+    }
 
-    oxygen * co2
+    fn diagnostics(&self, oxygen: Policy, co2: Policy) -> String {
+        let w = self.width;
+        let mut s = format!("{:>5} {:>8} {:>8}\n", "bit", "ones", "zeros");
+        for k in (0..w).rev() {
+            let ones = ones_bit_count(&self.xs, k);
+            s += &format!("{:>5} {:>8} {:>8}\n", k, ones, self.xs.len() - ones);
+        }
+
+        let gamma = self.rate(Policy::OXYGEN);
+        let epsilon = self.rate(Policy::CO2);
+        s += &format!("\ngamma   {:0w$b} = {}\n", gamma, gamma, w = w);
+        s += &format!("epsilon {:0w$b} = {}\n", epsilon, epsilon, w = w);
+
+        let sorted = self.sorted();
+        for (label, policy) in [("oxygen", oxygen), ("co2", co2)] {
+            let (value, survivors) = rating_rounds(&sorted, w, policy);
+            s += &format!("\n{} rating ({:?}, ties keep {:?})\n", label, policy.criteria, policy.tie);
+            for (round, count) in survivors.iter().enumerate() {
+                s += &format!("  round {:>3} (bit {:>3}): {} left\n", round + 1, w - 1 - round, count);
+            }
+            match value {
+                Some(v) => s += &format!("  {} = {:0w$b} = {}\n", label, v, v, w = w),
+                None => s += &format!("  {} = no single survivor\n", label),
+            }
+        }
+        s
+    }
+}
+
+// power consumption, gamma * epsilon
+fn part_a(report: &Report) -> U256 {
+    power_consumption(report, Policy::OXYGEN, Policy::CO2)
+}
+
+// life support rating, None when a rating has no single survivor
+fn part_b(report: &Report) -> Option<U256> {
+    life_support(report, Policy::OXYGEN, Policy::CO2)
+}
+
+fn power_consumption(report: &Report, gamma: Policy, epsilon: Policy) -> U256 {
+    U256::mul(report.rate(gamma), report.rate(epsilon))
+}
+
+fn life_support(report: &Report, oxygen: Policy, co2: Policy) -> Option<U256> {
+    let sorted = report.sorted();
+    let oxygen = rating(&sorted, report.width, oxygen)?;
+    let co2 = rating(&sorted, report.width, co2)?;
+    Some(U256::mul(oxygen, co2))
+}
+
+// the product of two 128 bit ratings, little endian 64 bit limbs
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct U256 {
+    limbs: [u64; 4],
 }
 
-fn dominate_bit(ones: usize, zeros: usize) -> &'static str {
-    if ones >= zeros { "1" } else { "0" }
+impl U256 {
+    fn mul(a: u128, b: u128) -> U256 {
+        let (a, b) = ([a as u64, (a >> 64) as u64], [b as u64, (b >> 64) as u64]);
+        let mut limbs = [0u64; 4];
+        for i in 0..2 {
+            let mut carry = 0u128;
+            for j in 0..2 {
+                let v = a[i] as u128 * b[j] as u128 + limbs[i + j] as u128 + carry;
+                limbs[i + j] = v as u64;
+                carry = v >> 64;
+            }
+            limbs[i + 2] = carry as u64;
+        }
+        U256 { limbs }
+    }
+
+    fn div_rem(&self, d: u64) -> (U256, u64) {
+        let mut limbs = [0u64; 4];
+        let mut rem = 0u128;
+        for i in (0..4).rev() {
+            let v = (rem << 64) | self.limbs[i] as u128;
+            limbs[i] = (v / d as u128) as u64;
+            rem = v % d as u128;
+        }
+        (U256 { limbs }, rem as u64)
+    }
 }
 
-fn filter(xs: &Vec<u16>, k: usize, use_dominate: bool) -> Option<u16> {
-    let len = xs.len();
-    if len == 0 {
-        None
-    } else if len == 1 {
-        Some(*xs.get(0).unwrap())
-    } else {
-        let ys = filter_by_kth_bit(xs, k - 1, use_dominate);
-        filter(&ys, k - 1, use_dominate)
+impl From<u128> for U256 {
+    fn from(v: u128) -> U256 {
+        U256::mul(v, 1)
     }
 }
 
-fn filter_by_kth_bit(xs: &Vec<u16>, k: usize, use_dominate: bool) -> Vec<u16> {
-    let len = xs.len();
-    xs.iter()
-        .filter(|x| {
-            let ones = ones_bit_count(xs, k);
-            let dominate_bit = dominate_bit(ones, len - ones);
-            let kth_bit = ((**x as usize & (1 << k)) >> k).to_string();
-            if use_dominate {
-                kth_bit == dominate_bit
-            } else {
-                kth_bit != dominate_bit
+impl fmt::Display for U256 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // base 10^19 chunks, least significant first
+        let mut chunks = Vec::new();
+        let mut n = *self;
+        while n.limbs != [0; 4] {
+            let (q, r) = n.div_rem(10_000_000_000_000_000_000);
+            chunks.push(r);
+            n = q;
+        }
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((first, rest)) => {
+                write!(f, "{}", first)?;
+                rest.iter().rev().try_for_each(|chunk| write!(f, "{:019}", chunk))
             }
-        })
-        .map(|x| *x)
-        .collect()
+        }
+    }
+}
+
+fn rating(xs: &[u128], width: usize, policy: Policy) -> Option<u128> {
+    rating_rounds(xs, width, policy).0
+}
+
+// xs must be sorted, the survivors of each round share their leading bits so they
+// stay a contiguous range split in two by the next bit, O(bits * log n) after sorting.
+// also returns the number of survivors after each round
+fn rating_rounds(xs: &[u128], width: usize, policy: Policy) -> (Option<u128>, Vec<usize>) {
+    let (mut lo, mut hi) = (0, xs.len());
+    let mut survivors = Vec::new();
+    for k in (0..width).rev() {
+        if hi - lo <= 1 {
+            break;
+        }
+        let split = lo + xs[lo..hi].partition_point(|x| (x >> k) & 1 == 0);
+        if policy.select(hi - split, split - lo) == 1 { lo = split } else { hi = split }
+        survivors.push(hi - lo);
+    }
+    let value = if hi - lo == 1 { Some(xs[lo]) } else { None };
+    (value, survivors)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part_a, part_b};
+    use crate::{life_support, part_a, part_b, power_consumption, rating, rating_rounds, Criteria, Policy, Report, TieBreak, U256};
+    use utils::ones_bit_count;
+
+    // reference implementation, recounts the kth bit for every element
+    fn filter(xs: &[u128], k: usize, policy: Policy) -> Option<u128> {
+        let len = xs.len();
+        if len == 0 || (len > 1 && k == 0) {
+            None
+        } else if len == 1 {
+            Some(xs[0])
+        } else {
+            let ys = filter_by_kth_bit(xs, k - 1, policy);
+            filter(&ys, k - 1, policy)
+        }
+    }
+
+    fn filter_by_kth_bit(xs: &[u128], k: usize, policy: Policy) -> Vec<u128> {
+        let len = xs.len();
+        xs.iter()
+            .filter(|x| {
+                let ones = ones_bit_count(xs, k);
+                (**x >> k) & 1 == policy.select(ones, len - ones)
+            })
+            .copied()
+            .collect()
+    }
 
     static TS: [&str; 12] = [
         "00100",
@@ -94,17 +297,98 @@ mod tests {
 
     #[test]
     fn test_part_a() {
-        let xs: Vec<u16> = TS.iter()
-            .map(|s| u16::from_str_radix(s, 2).unwrap())
-            .collect();
-        assert_eq!(198, part_a(&xs, 5));
+        let report = Report::new(&TS.join("\n")).unwrap();
+        assert_eq!(5, report.width);
+        assert_eq!(U256::from(198), part_a(&report));
     }
 
     #[test]
     fn test_part_b() {
-        let xs: Vec<u16> = TS.iter()
-            .map(|s| u16::from_str_radix(s, 2).unwrap())
-            .collect();
-        assert_eq!(230, part_b(&xs, 5));
+        let report = Report::new(&TS.join("\n")).unwrap();
+        assert_eq!(Some(U256::from(230)), part_b(&report));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_wide_report() {
+        // the sample shifted to the top of 64 bits
+        let shifted: Vec<String> = TS.iter().map(|s| format!("{:0<64}", s)).collect();
+        let report = Report::new(&shifted.join("\n")).unwrap();
+        assert_eq!(64, report.width);
+        assert_eq!(U256::from((22 << 59) * ((9 << 59) | ((1 << 59) - 1))), part_a(&report));
+        assert_eq!(Some(U256::from(230 << 118)), part_b(&report));
+
+        // the sample shifted to the top of 128 bits
+        let shifted: Vec<String> = TS.iter().map(|s| format!("{:0<128}", s)).collect();
+        let report = Report::new(&shifted.join("\n")).unwrap();
+        assert_eq!(128, report.width);
+        let power = "24877206672079651360532828810460292702617029140750569736721145271590745800704";
+        assert_eq!(power, part_a(&report).to_string());
+        let rating = "26007988793537817331466139210935760552980562962009111063550043283027363102720";
+        assert_eq!(rating, part_b(&report).unwrap().to_string());
+
+        assert_eq!("0", U256::from(0).to_string());
+        assert_eq!("10000000000000000000", U256::from(10_000_000_000_000_000_000).to_string());
+        let max = "115792089237316195423570985008687907852589419931798687112530834793049593217025";
+        assert_eq!(max, U256::mul(u128::MAX, u128::MAX).to_string());
+    }
+
+    #[test]
+    fn test_invalid_report() {
+        assert!(Report::new("").is_err());
+        assert!(Report::new("0101\n011\n").is_err());
+        assert!(Report::new("0101\n0121\n").is_err());
+        assert_eq!(Err("line 2: \"+0101\" is not binary".to_string()), Report::new("01010\n+0101\n").map(|r| r.width));
+        assert!(Report::new(&"1".repeat(129)).is_err());
+    }
+
+    #[test]
+    fn test_rating_matches_filter() {
+        let mut seed: u64 = 2021;
+        for width in [1, 3, 8, 12, 20] {
+            for len in [1, 2, 5, 50, 300] {
+                let xs: Vec<u128> = (0..len)
+                    .map(|_| {
+                        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        ((seed >> 33) as u128) & ((1 << width) - 1)
+                    })
+                    .collect();
+                let mut sorted = xs.clone();
+                sorted.sort_unstable();
+                for policy in ["most:1", "most:0", "least:1", "least:0"] {
+                    let policy = Policy::parse(policy).unwrap();
+                    assert_eq!(filter(&xs, width, policy), rating(&sorted, width, policy),
+                               "width {}, len {}, {:?}", width, len, policy);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_policies() {
+        assert_eq!(Ok(Policy::OXYGEN), Policy::parse("most:1"));
+        assert_eq!(Ok(Policy { criteria: Criteria::LeastCommon, tie: TieBreak::One }), Policy::parse("least:1"));
+        assert!(Policy::parse("most").is_err());
+        assert!(Policy::parse("mode:1").is_err());
+
+        assert_eq!(1, Policy::OXYGEN.select(3, 3));
+        assert_eq!(0, Policy::CO2.select(3, 3));
+        assert_eq!(0, Policy::parse("most:0").unwrap().select(3, 3));
+        assert_eq!(1, Policy::parse("least:0").unwrap().select(2, 3));
+
+        let report = Report::new(&TS.join("\n")).unwrap();
+        assert_eq!(U256::from(198), power_consumption(&report, Policy::OXYGEN, Policy::CO2));
+        assert_eq!(Some(U256::from(230)), life_support(&report, Policy::OXYGEN, Policy::CO2));
+
+        let (value, survivors) = rating_rounds(&report.sorted(), report.width, Policy::OXYGEN);
+        assert_eq!(Some(23), value);
+        assert_eq!(vec![7, 4, 3, 2, 1], survivors);
+        let (value, survivors) = rating_rounds(&report.sorted(), report.width, Policy::CO2);
+        assert_eq!(Some(10), value);
+        assert_eq!(vec![5, 2, 1], survivors);
+
+        let diagnostics = report.diagnostics(Policy::OXYGEN, Policy::CO2);
+        assert!(diagnostics.contains("gamma   10110 = 22\n"));
+        assert!(diagnostics.contains("epsilon 01001 = 9\n"));
+        assert!(diagnostics.contains("co2 = 01010 = 10\n"));
+    }
+}
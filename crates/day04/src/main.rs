@@ -1,165 +1,632 @@
-use utils::InputType;
-use std::convert::TryInto;
+use utils::{InputType, Rng};
+use std::collections::HashMap;
 
 fn main() {
-    let (moves, mut board) = read_input(InputType::Input);
-    println!("Day04 part a = {}", part_a(&moves, &mut board)); // 21607
-    println!("Day04 part b = {}", part_b(&moves, &mut board)); // 19012
-}
+    let mut game = read_input(InputType::Input);
+    println!("Day04 part a = {}", part_a(&game)); // 21607
+    println!("Day04 part b = {}", part_b(&game)); // 19012
 
-const BOARD_SIZE: usize = 5;
+    let ranking = game.ranking();
+    let marked = game.events().iter().filter(|event| matches!(event, Event::Marked { .. })).count();
+    println!("{} cells marked, {} of {} boards win, never winning: {:?}", marked, ranking.len(), game.boards.len(), game.never_win());
+    for (i, win) in ranking.iter().enumerate().take(3) {
+        println!("  winner {:>3}: board {:>3} at turn {:>2} with score {}", i + 1, win.board, win.turn, win.score);
+    }
 
-fn part_a(draws: &Vec<u16>, boards: &mut Vec<Board>) -> usize {
-    for draw in draws {
-        apply_draw(boards, *draw);
-        if let Some(_winner_count) = set_winners(boards) {
-            return  boards.iter().find(|b| b.winner).unwrap().score(*draw);
+    // --rules rows,columns,diagonals,corners,blackout,mask:10001/01010/00100/01010/10001
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(rules) = args.iter().position(|arg| arg == "--rules").and_then(|i| args.get(i + 1)) {
+        match rules.split(',').map(WinRule::parse).collect::<Result<Vec<WinRule>, String>>() {
+            Ok(rules) => {
+                game = game.with_rules(rules);
+                println!("{:?}: part a = {}, part b = {}", game.rules, part_a(&game), part_b(&game));
+            }
+            Err(e) => println!("{}", e),
         }
     }
-    0
-}
 
-fn part_b(draws: &Vec<u16>, boards: &mut Vec<Board>) -> usize {
-    for draw in draws {
-        apply_draw(boards, *draw);
-        if let Some(winner_count) = set_winners(boards) {
-            if boards.len() > 0 && boards.len() == winner_count {
-                return boards.get(boards.len() - 1).unwrap().score(*draw);
+    // --simulate <trials> [seed] [--generate <cards>]
+    if let Some(i) = args.iter().position(|arg| arg == "--simulate") {
+        let trials = args.get(i + 1).and_then(|s| s.parse().ok()).unwrap_or(1000);
+        let seed = args.get(i + 2).and_then(|s| s.parse().ok()).unwrap_or(2021);
+        let mut rng = Rng::new(seed);
+        let card_rules = CardRules::default();
+        if let Some(count) = args.iter().position(|arg| arg == "--generate").and_then(|i| args.get(i + 1)) {
+            let boards = count
+                .parse()
+                .map_err(|_| format!("invalid card count {:?}", count))
+                .and_then(|count| generate_cards(&card_rules, count, &mut rng));
+            match boards {
+                Ok(boards) => game = BingoGame::new(vec![], boards).with_rules(game.rules.clone()),
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
             }
         }
 
-        // remove winners
-        boards.retain(|b| !b.winner);
+        match monte_carlo(&game, &card_rules.numbers(), trials, &mut rng) {
+            Ok(mut stats) => {
+                stats.sort_by(|a, b| b.first.total_cmp(&a.first));
+                println!("{:>6} {:>8} {:>8} {:>10} {:>10}", "board", "first", "wins", "turn", "score");
+                for s in stats.iter().take(10) {
+                    println!("{:>6} {:>8.4} {:>8.4} {:>10.2} {:>10.1}",
+                             s.board, s.first, s.win_rate, s.expected_turn.unwrap_or(f64::NAN), s.expected_score.unwrap_or(f64::NAN));
+                }
+            }
+            Err(e) => println!("{}", e),
+        }
     }
+}
 
+fn part_a(game: &BingoGame) -> usize {
+    game.nth_winner(0).map_or(0, |win| win.score)
+}
+
+fn part_b(game: &BingoGame) -> usize {
     // no loser board
-    0
+    game.ranking().last().map_or(0, |win| win.score)
 }
 
+#[derive(Debug, Clone)]
 struct Board {
-    elements: [[(u16, bool); BOARD_SIZE]; BOARD_SIZE],
-    winner: bool,
+    rows: usize,
+    cols: usize,
+    elements: Vec<u16>,
 }
 
 impl Board {
-    fn new(data: &[u16; BOARD_SIZE * BOARD_SIZE]) -> Board {
-        let mut elements = [[(0, false); BOARD_SIZE]; BOARD_SIZE];
-        let mut idx = 0;
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                elements[i][j].0 = data[idx];
-                idx += 1;
+    fn new(rows: usize, cols: usize, data: &[u16]) -> Board {
+        assert_eq!(rows * cols, data.len());
+        Board { rows, cols, elements: data.to_vec() }
+    }
+}
+
+// win patterns for one board size, and for each cell the patterns it belongs to
+#[derive(Debug, Clone)]
+struct Layout {
+    rows: usize,
+    cols: usize,
+    patterns: Vec<Vec<usize>>,
+    cell_patterns: Vec<Vec<usize>>,
+}
+
+impl Layout {
+    fn new(rows: usize, cols: usize, rules: &[WinRule]) -> Layout {
+        let patterns: Vec<Vec<usize>> = rules.iter().flat_map(|rule| rule.patterns(rows, cols)).collect();
+        let mut cell_patterns = vec![Vec::new(); rows * cols];
+        for (p, pattern) in patterns.iter().enumerate() {
+            for idx in pattern {
+                cell_patterns[*idx].push(p);
             }
         }
-        Board { elements, winner: false }
+        Layout { rows, cols, patterns, cell_patterns }
     }
+}
 
-    fn set_winner(&mut self) -> bool {
-        // row winner
-        for i in 0..BOARD_SIZE {
-            if self.elements[i].iter().all(|(_v, b)| *b) {
-                return true;
+#[derive(Debug, Clone, PartialEq)]
+enum WinRule {
+    Rows,
+    Columns,
+    // both main diagonals, square boards only
+    Diagonals,
+    FourCorners,
+    Blackout,
+    // row major, a board wins when every true cell is marked, boards of another size ignore it
+    Mask { rows: usize, cols: usize, cells: Vec<bool> },
+}
+
+impl WinRule {
+    fn parse(s: &str) -> Result<WinRule, String> {
+        match s {
+            "rows" => Ok(WinRule::Rows),
+            "columns" => Ok(WinRule::Columns),
+            "diagonals" => Ok(WinRule::Diagonals),
+            "corners" => Ok(WinRule::FourCorners),
+            "blackout" => Ok(WinRule::Blackout),
+            _ => match s.strip_prefix("mask:") {
+                Some(mask) => WinRule::mask(mask),
+                None => Err(format!("unknown win rule {:?}", s)),
             }
         }
+    }
 
-        // column winner
-        for j in 0..BOARD_SIZE {
-            let mut count = 0;
-            for i in 0..BOARD_SIZE {
-                if self.elements[i][j].1 {
-                    count += 1;
-                }
+    // rows separated by '/', e.g. "101/010/101"
+    fn mask(s: &str) -> Result<WinRule, String> {
+        let lines: Vec<&str> = s.split('/').collect();
+        let cols = lines[0].len();
+        if cols == 0 || lines.iter().any(|line| line.len() != cols) {
+            return Err(format!("mask {:?} rows must have the same, non zero length", s));
+        }
+        let cells = lines
+            .iter()
+            .flat_map(|line| line.chars())
+            .map(|ch| match ch {
+                '1' => Ok(true),
+                '0' => Ok(false),
+                _ => Err(format!("mask {:?} may only contain 0, 1 and /", s)),
+            })
+            .collect::<Result<Vec<bool>, String>>()?;
+        if !cells.iter().any(|b| *b) {
+            return Err(format!("mask {:?} has no cells set", s));
+        }
+        Ok(WinRule::Mask { rows: lines.len(), cols, cells })
+    }
+
+    // each pattern is a list of row major cell indices that must all be marked
+    fn patterns(&self, rows: usize, cols: usize) -> Vec<Vec<usize>> {
+        // a board without cells has nothing to complete
+        if rows == 0 || cols == 0 {
+            return vec![];
+        }
+        match self {
+            WinRule::Rows => (0..rows).map(|r| (0..cols).map(|c| r * cols + c).collect()).collect(),
+            WinRule::Columns => (0..cols).map(|c| (0..rows).map(|r| r * cols + c).collect()).collect(),
+            WinRule::Diagonals if rows == cols => vec![
+                (0..rows).map(|i| i * cols + i).collect(),
+                (0..rows).map(|i| i * cols + cols - 1 - i).collect(),
+            ],
+            WinRule::Diagonals => vec![],
+            WinRule::FourCorners => {
+                let mut corners = vec![0, cols - 1, (rows - 1) * cols, rows * cols - 1];
+                corners.sort_unstable();
+                corners.dedup();
+                vec![corners]
             }
-            if count == BOARD_SIZE {
-                self.winner = true;
-                return true;
+            WinRule::Blackout => vec![(0..rows * cols).collect()],
+            WinRule::Mask { rows: r, cols: c, cells } if *r == rows && *c == cols => {
+                vec![(0..rows * cols).filter(|idx| cells[*idx]).collect()]
+            }
+            WinRule::Mask { .. } => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Event {
+    Marked { turn: usize, draw: u16, board: usize, row: usize, col: usize },
+    Won(Win),
+}
+
+// turn is the 1-based index of the winning draw
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Win {
+    board: usize,
+    turn: usize,
+    draw: u16,
+    score: usize,
+}
+
+// the draws and the unmarked boards, every query replays the game from the start
+#[derive(Debug, Clone)]
+struct BingoGame {
+    draws: Vec<u16>,
+    boards: Vec<Board>,
+    rules: Vec<WinRule>,
+    // drawn number -> (board, row major cell) of every cell holding it
+    index: HashMap<u16, Vec<(usize, usize)>>,
+    layouts: Vec<Layout>,
+    board_layout: Vec<usize>,
+}
+
+impl BingoGame {
+    fn new(draws: Vec<u16>, boards: Vec<Board>) -> BingoGame {
+        let mut index: HashMap<u16, Vec<(usize, usize)>> = HashMap::new();
+        for (b, board) in boards.iter().enumerate() {
+            for (idx, v) in board.elements.iter().enumerate() {
+                index.entry(*v).or_default().push((b, idx));
             }
         }
+        let game = BingoGame { draws, boards, rules: vec![], index, layouts: vec![], board_layout: vec![] };
+        game.with_rules(vec![WinRule::Rows, WinRule::Columns])
+    }
 
-        // not a winner
-        false
+    fn with_rules(self, rules: Vec<WinRule>) -> BingoGame {
+        let mut layouts: Vec<Layout> = Vec::new();
+        let mut board_layout = Vec::with_capacity(self.boards.len());
+        for board in &self.boards {
+            let l = match layouts.iter().position(|l| l.rows == board.rows && l.cols == board.cols) {
+                Some(l) => l,
+                None => {
+                    layouts.push(Layout::new(board.rows, board.cols, &rules));
+                    layouts.len() - 1
+                }
+            };
+            board_layout.push(l);
+        }
+        BingoGame { rules, layouts, board_layout, ..self }
+    }
+
+    // each draw only touches the cells holding it, every touched cell bumps the hit
+    // counters of its patterns and a pattern is complete when its counter reaches its size.
+    // boards drop out of the game once they have won
+    fn replay<F: FnMut(Event)>(&self, on_event: F) {
+        self.replay_draws(&self.draws, on_event)
     }
 
-    fn score(&self, draw: u16) -> usize {
-        let mut sum = 0;
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                if !self.elements[i][j].1 {
-                    sum += self.elements[i][j].0;
+    fn replay_draws<F: FnMut(Event)>(&self, draws: &[u16], mut on_event: F) {
+        let mut marked: Vec<Vec<bool>> = self.boards.iter().map(|board| vec![false; board.elements.len()]).collect();
+        let mut hits: Vec<Vec<usize>> = self.board_layout.iter().map(|l| vec![0; self.layouts[*l].patterns.len()]).collect();
+        let mut unmarked: Vec<usize> = self.boards.iter().map(|board| board.elements.iter().map(|v| *v as usize).sum()).collect();
+        let mut winner = vec![false; self.boards.len()];
+        let mut new_winners = Vec::new();
+
+        for (i, draw) in draws.iter().enumerate() {
+            let turn = i + 1;
+            for (b, idx) in self.index.get(draw).map_or(&[][..], |cells| &cells[..]) {
+                let (b, idx) = (*b, *idx);
+                if winner[b] || marked[b][idx] {
+                    continue;
                 }
+                marked[b][idx] = true;
+                unmarked[b] -= *draw as usize;
+
+                let layout = &self.layouts[self.board_layout[b]];
+                on_event(Event::Marked { turn, draw: *draw, board: b, row: idx / layout.cols, col: idx % layout.cols });
+                for p in &layout.cell_patterns[idx] {
+                    hits[b][*p] += 1;
+                    if hits[b][*p] == layout.patterns[*p].len() && new_winners.last() != Some(&b) {
+                        new_winners.push(b);
+                    }
+                }
+            }
+
+            // a number can appear more than once on a board, score after all of them are marked
+            for b in new_winners.drain(..) {
+                winner[b] = true;
+                on_event(Event::Won(Win { board: b, turn, draw: *draw, score: unmarked[b] * *draw as usize }));
             }
         }
-        sum as usize * draw as usize
+    }
+
+    fn events(&self) -> Vec<Event> {
+        let mut events = Vec::new();
+        self.replay(|event| events.push(event));
+        events
+    }
+
+    // winning boards in the order they win, boards winning on the same turn by index
+    fn ranking(&self) -> Vec<Win> {
+        let mut wins = Vec::new();
+        self.replay(|event| if let Event::Won(win) = event { wins.push(win) });
+        wins
+    }
+
+    fn nth_winner(&self, n: usize) -> Option<Win> {
+        self.ranking().get(n).copied()
+    }
+
+    fn never_win(&self) -> Vec<usize> {
+        let mut won = vec![false; self.boards.len()];
+        for win in self.ranking() {
+            won[win.board] = true;
+        }
+        (0..self.boards.len()).filter(|b| !won[*b]).collect()
     }
 }
 
-fn read_input(input_type: InputType) -> (Vec<u16>, Vec<Board>) {
+// random cards, numbers are drawn from min..=max
+#[derive(Debug, Clone)]
+struct CardRules {
+    rows: usize,
+    cols: usize,
+    min: u16,
+    max: u16,
+    unique: bool,
+}
+
+impl Default for CardRules {
+    fn default() -> CardRules {
+        CardRules { rows: 5, cols: 5, min: 0, max: 99, unique: true }
+    }
+}
+
+impl CardRules {
+    fn numbers(&self) -> Vec<u16> {
+        (self.min..=self.max).collect()
+    }
+}
+
+fn generate_cards(rules: &CardRules, count: usize, rng: &mut Rng) -> Result<Vec<Board>, String> {
+    if rules.rows == 0 || rules.cols == 0 {
+        return Err(format!("a {}x{} card has no cells", rules.rows, rules.cols));
+    }
+    let cells = rules.rows * rules.cols;
+    let mut numbers = rules.numbers();
+    if numbers.is_empty() || (rules.unique && numbers.len() < cells) {
+        return Err(format!("{}..={} has too few numbers for a {}x{} card", rules.min, rules.max, rules.rows, rules.cols));
+    }
+
+    let cards = (0..count)
+        .map(|_| {
+            let data: Vec<u16> = if rules.unique {
+                rng.shuffle(&mut numbers);
+                numbers[..cells].to_vec()
+            } else {
+                (0..cells).map(|_| numbers[rng.below(numbers.len() as u64) as usize]).collect()
+            };
+            Board::new(rules.rows, rules.cols, &data)
+        })
+        .collect();
+    Ok(cards)
+}
+
+// first is the chance of being the first winner, boards winning together share the credit.
+// turn and score are averaged over the trials the board wins
+#[derive(Debug, Clone, PartialEq)]
+struct CardStats {
+    board: usize,
+    first: f64,
+    win_rate: f64,
+    expected_turn: Option<f64>,
+    expected_score: Option<f64>,
+}
+
+// plays the game's boards and rules against random orderings of numbers
+fn monte_carlo(game: &BingoGame, numbers: &[u16], trials: usize, rng: &mut Rng) -> Result<Vec<CardStats>, String> {
+    if trials == 0 {
+        return Err("at least one trial is needed".to_string());
+    }
+    let n = game.boards.len();
+    let mut first = vec![0.0; n];
+    let mut wins = vec![0usize; n];
+    let mut turns = vec![0usize; n];
+    let mut scores = vec![0usize; n];
+    let mut draws = numbers.to_vec();
+
+    for _ in 0..trials {
+        rng.shuffle(&mut draws);
+        let mut ranking = Vec::new();
+        game.replay_draws(&draws, |event| if let Event::Won(win) = event { ranking.push(win) });
+
+        let first_turn = ranking.first().map_or(0, |win| win.turn);
+        let tied = ranking.iter().take_while(|win| win.turn == first_turn).count();
+        for win in &ranking {
+            if win.turn == first_turn {
+                first[win.board] += 1.0 / tied as f64;
+            }
+            wins[win.board] += 1;
+            turns[win.board] += win.turn;
+            scores[win.board] += win.score;
+        }
+    }
+
+    let mean = |total: usize, count: usize| if count == 0 { None } else { Some(total as f64 / count as f64) };
+    let stats = (0..n)
+        .map(|b| CardStats {
+            board: b,
+            first: first[b] / trials as f64,
+            win_rate: wins[b] as f64 / trials as f64,
+            expected_turn: mean(turns[b], wins[b]),
+            expected_score: mean(scores[b], wins[b]),
+        })
+        .collect();
+    Ok(stats)
+}
+
+fn read_input(input_type: InputType) -> BingoGame {
     let data = {
         match input_type {
             InputType::Sample => include_str!("sample.txt"),
             InputType::Input => include_str!("input.txt"),
         }
     };
-    let moves = data
-        .lines()
-        .take(1)
-        .flat_map(|xs| xs
-            .split(",")
-            .map(|x| x.parse().unwrap()))
-        .collect();
 
-    let bs: Vec<u16> = data
-        .lines()
-        .skip(2)
-        .filter(|s| s.len() > 0)
-        .flat_map(|xs| xs
-            .split(" ")
-            .filter(|s| s.len() > 0)
-            .map(|x| x.parse().unwrap()))
-        .collect();
-
-    let boards: Vec<Board> = bs[..]
-        .chunks(BOARD_SIZE * BOARD_SIZE)
-        .map(|chunk| Board::new(chunk.try_into().expect("slice with incorrect length")))
-        .collect();
-
-    (moves, boards)
+    load_input(data).unwrap()
 }
 
-fn apply_draw(boards: &mut Vec<Board>, draw: u16) {
-    for board in boards {
-        for i in 0..BOARD_SIZE {
-            for j in 0..BOARD_SIZE {
-                if board.elements[i][j].0 == draw {
-                    board.elements[i][j].1 = true
+// draws on the first line, then boards separated by blank lines, each board's size
+// is taken from its number of lines and numbers per line
+fn load_input(data: &str) -> Result<BingoGame, String> {
+    let moves = data
+        .lines()
+        .next()
+        .ok_or("missing draws")?
+        .split(',')
+        .map(|x| x.trim().parse().map_err(|_| format!("invalid draw {:?}", x)))
+        .collect::<Result<Vec<u16>, String>>()?;
+
+    let mut boards = Vec::new();
+    let mut rows: Vec<Vec<u16>> = Vec::new();
+    // a blank line past the end closes the last board
+    for (i, line) in data.lines().enumerate().skip(1).chain([(data.lines().count(), "")]) {
+        if line.trim().is_empty() {
+            if !rows.is_empty() {
+                let cols = rows[0].len();
+                if rows.iter().any(|row| row.len() != cols) {
+                    return Err(format!("board {} ending before line {} has rows of different lengths", boards.len(), i + 1));
                 }
+                boards.push(Board::new(rows.len(), cols, &rows.concat()));
+                rows.clear();
             }
+            continue;
         }
+        let row = line
+            .split_whitespace()
+            .map(|x| x.parse().map_err(|_| format!("line {}: invalid number {:?}", i + 1, x)))
+            .collect::<Result<Vec<u16>, String>>()?;
+        rows.push(row);
     }
-}
 
-fn set_winners(boards: &mut Vec<Board>) -> Option<usize> {
-    for board in boards.into_iter() {
-        board.winner = board.set_winner()
-    }
-    let winner_count = boards.iter().fold(0, |acc, b| if b.winner { acc + 1 } else { acc });
-    if winner_count > 0 { Some(winner_count) } else { None }
+    Ok(BingoGame::new(moves, boards))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{read_input, part_a, part_b};
-    use utils::InputType;
+    use crate::{generate_cards, load_input, monte_carlo, read_input, part_a, part_b, BingoGame, Board, CardRules, Event, Win, WinRule};
+    use utils::{InputType, Rng};
 
     #[test]
     fn test_part_a() {
-        let (moves, mut board) = read_input(InputType::Sample);
-        assert_eq!(4512, part_a(&moves, &mut board));
+        let game = read_input(InputType::Sample);
+        assert_eq!(4512, part_a(&game));
     }
 
     #[test]
     fn test_part_b() {
-        let (moves, mut board) = read_input(InputType::Sample);
-        assert_eq!(1924, part_b(&moves, &mut board));
+        let game = read_input(InputType::Sample);
+        assert_eq!(1924, part_b(&game));
+        // part b no longer depends on part a having run first
+        assert_eq!(4512, part_a(&game));
+    }
+
+    #[test]
+    fn test_ranking() {
+        let game = read_input(InputType::Sample);
+        let ranking = game.ranking();
+        assert_eq!(vec![2, 0, 1], ranking.iter().map(|win| win.board).collect::<Vec<usize>>());
+        assert_eq!(Some(Win { board: 2, turn: 12, draw: 24, score: 4512 }), game.nth_winner(0));
+        assert_eq!(Some(Win { board: 1, turn: 15, draw: 13, score: 1924 }), game.nth_winner(2));
+        assert_eq!(None, game.nth_winner(3));
+        assert!(game.never_win().is_empty());
+
+        let events = game.events();
+        assert_eq!(Some(&Event::Marked { turn: 1, draw: 7, board: 0, row: 2, col: 4 }), events.first());
+        assert_eq!(3, events.iter().filter(|e| matches!(e, Event::Won(_))).count());
+
+        let early = BingoGame::new(game.draws[..12].to_vec(), game.boards.clone());
+        assert_eq!(vec![0, 1], early.never_win());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_board_sizes() {
+        let game = load_input("1,2,3,4,5,6\n\n1 2 3\n4 5 6\n\n6 5\n4 3\n2 1\n").unwrap();
+        assert_eq!((2, 3), (game.boards[0].rows, game.boards[0].cols));
+        assert_eq!((3, 2), (game.boards[1].rows, game.boards[1].cols));
+        assert_eq!(Some(Win { board: 1, turn: 2, draw: 2, score: 36 }), game.nth_winner(0));
+        assert_eq!(Some(Win { board: 0, turn: 3, draw: 3, score: 45 }), game.nth_winner(1));
+
+        let err = |data: &str| load_input(data).map(|game| game.boards.len()).unwrap_err();
+        assert_eq!("board 0 ending before line 5 has rows of different lengths", err("1,2\n\n1 2\n3\n"));
+        assert_eq!("board 0 ending before line 5 has rows of different lengths", err("1,2\n\n1 2\n3\n\n4 5\n"));
+        assert_eq!("invalid draw \"x\"", err("1,x\n\n1 2\n3 4\n"));
+    }
+
+    #[test]
+    fn test_win_rules() {
+        assert_eq!(vec![vec![0, 4, 8], vec![2, 4, 6]], WinRule::Diagonals.patterns(3, 3));
+        assert!(WinRule::Diagonals.patterns(2, 3).is_empty());
+        assert_eq!(vec![vec![0, 2, 3, 5]], WinRule::FourCorners.patterns(2, 3));
+        assert_eq!(vec![vec![0, 2]], WinRule::FourCorners.patterns(1, 3));
+        assert!(WinRule::FourCorners.patterns(0, 3).is_empty());
+        assert!(WinRule::Blackout.patterns(2, 0).is_empty());
+        assert_eq!(vec![vec![1, 3]], WinRule::parse("mask:010/100").unwrap().patterns(2, 3));
+        assert!(WinRule::parse("mask:010/100").unwrap().patterns(3, 3).is_empty());
+        assert!(WinRule::parse("mask:01/100").is_err());
+        assert!(WinRule::parse("mask:00/00").is_err());
+        assert!(WinRule::parse("mask:02/00").is_err());
+        assert!(WinRule::parse("triangle").is_err());
+
+        let game = read_input(InputType::Sample);
+        let blackout = game.clone().with_rules(vec![WinRule::Blackout]);
+        assert!(blackout.ranking().iter().all(|win| win.score == 0));
+
+        let small = load_input("1,4\n\n1 2\n3 4\n").unwrap();
+        assert_eq!(vec![0], small.never_win());
+        let small = small.with_rules(vec![WinRule::Diagonals]);
+        assert_eq!(Some(Win { board: 0, turn: 2, draw: 4, score: 20 }), small.nth_winner(0));
+
+        let corners = game.with_rules(vec![WinRule::FourCorners]);
+        let first = corners.nth_winner(0).unwrap();
+        let board = &corners.boards[first.board];
+        let draws = &corners.draws[..first.turn];
+        assert!([0, 4, 20, 24].iter().all(|idx| draws.contains(&board.elements[*idx])));
+    }
+
+    // reference replay, scans every cell of every board and rechecks every pattern on each draw
+    fn scan_ranking(game: &BingoGame) -> Vec<Win> {
+        let mut marked: Vec<Vec<bool>> = game.boards.iter().map(|board| vec![false; board.elements.len()]).collect();
+        let mut wins: Vec<Win> = Vec::new();
+        for (i, draw) in game.draws.iter().enumerate() {
+            for (b, board) in game.boards.iter().enumerate() {
+                if wins.iter().any(|win| win.board == b) {
+                    continue;
+                }
+                for (idx, v) in board.elements.iter().enumerate() {
+                    if v == draw {
+                        marked[b][idx] = true;
+                    }
+                }
+                let patterns: Vec<Vec<usize>> = game.rules.iter().flat_map(|rule| rule.patterns(board.rows, board.cols)).collect();
+                if patterns.iter().any(|pattern| pattern.iter().all(|idx| marked[b][*idx])) {
+                    let sum: usize = board.elements.iter().zip(&marked[b]).filter(|(_, m)| !**m).map(|(v, _)| *v as usize).sum();
+                    wins.push(Win { board: b, turn: i + 1, draw: *draw, score: sum * *draw as usize });
+                }
+            }
+        }
+        wins
+    }
+
+    #[test]
+    fn test_indexed_replay() {
+        let game = read_input(InputType::Sample);
+        for rules in ["rows,columns", "diagonals", "corners", "blackout", "mask:10001/01010/00100/01010/10001"] {
+            let rules: Vec<WinRule> = rules.split(',').map(|rule| WinRule::parse(rule).unwrap()).collect();
+            let game = game.clone().with_rules(rules);
+            assert_eq!(scan_ranking(&game), game.ranking(), "{:?}", game.rules);
+        }
+
+        // repeated numbers on a board are all marked before it is scored
+        let game = BingoGame::new(vec![1, 2, 3], vec![Board::new(2, 2, &[1, 2, 1, 3]), Board::new(1, 3, &[3, 2, 1])]);
+        assert_eq!(scan_ranking(&game), game.ranking());
+        assert_eq!(Some(Win { board: 0, turn: 1, draw: 1, score: 5 }), game.nth_winner(0));
+
+        // many boards filled from 0..97 with different strides
+        let boards: Vec<Board> = (0..500)
+            .map(|i| {
+                let data: Vec<u16> = (0..25).map(|j| ((i * 7 + j * 11) % 97) as u16).collect();
+                Board::new(5, 5, &data)
+            })
+            .collect();
+        let draws: Vec<u16> = (0..97).map(|i| (i * 31 % 97) as u16).collect();
+        let game = BingoGame::new(draws, boards);
+        assert_eq!(scan_ranking(&game), game.ranking());
+    }
+
+    #[test]
+    fn test_generate_cards() {
+        let rules = CardRules { rows: 3, cols: 4, min: 1, max: 20, unique: true };
+        let cards = generate_cards(&rules, 10, &mut Rng::new(7)).unwrap();
+        assert_eq!(10, cards.len());
+        for card in &cards {
+            assert_eq!((3, 4), (card.rows, card.cols));
+            assert!(card.elements.iter().all(|v| (1..=20).contains(v)));
+            let mut xs = card.elements.clone();
+            xs.sort_unstable();
+            xs.dedup();
+            assert_eq!(12, xs.len());
+        }
+
+        let again = generate_cards(&rules, 10, &mut Rng::new(7)).unwrap();
+        assert!(cards.iter().zip(&again).all(|(a, b)| a.elements == b.elements));
+
+        let repeats = CardRules { unique: false, max: 3, ..rules.clone() };
+        assert!(generate_cards(&repeats, 2, &mut Rng::new(7)).is_ok());
+        assert!(generate_cards(&CardRules { max: 5, ..rules.clone() }, 2, &mut Rng::new(7)).is_err());
+        assert_eq!(Err("a 0x4 card has no cells".to_string()), generate_cards(&CardRules { rows: 0, ..rules.clone() }, 2, &mut Rng::new(7)).map(|c| c.len()));
+        assert!(generate_cards(&CardRules { cols: 0, ..rules }, 2, &mut Rng::new(7)).is_err());
+    }
+
+    #[test]
+    fn test_monte_carlo() {
+        let game = read_input(InputType::Sample);
+        let numbers: Vec<u16> = (0..=26).collect();
+        let stats = monte_carlo(&game, &numbers, 500, &mut Rng::new(1)).unwrap();
+        assert_eq!(3, stats.len());
+        assert!((stats.iter().map(|s| s.first).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(stats.iter().all(|s| s.win_rate == 1.0));
+        assert!(stats.iter().all(|s| s.expected_turn.unwrap() >= 5.0 && s.expected_turn.unwrap() <= 27.0));
+
+        // same seed, same estimate
+        assert_eq!(stats, monte_carlo(&game, &numbers, 500, &mut Rng::new(1)).unwrap());
+        assert!(monte_carlo(&game, &numbers, 0, &mut Rng::new(1)).is_err());
+
+        // a single row card whose numbers are a subset of another's never wins after it,
+        // they tie whenever 3 is not the last of 1, 2, 3 drawn so first is 1/3 + 2/3 * 1/2
+        let boards = vec![Board::new(1, 2, &[1, 2]), Board::new(1, 3, &[1, 2, 3])];
+        let game = BingoGame::new(vec![], boards).with_rules(vec![WinRule::Rows]);
+        let stats = monte_carlo(&game, &[1, 2, 3, 4], 200, &mut Rng::new(3)).unwrap();
+        assert!((stats[0].first - 2.0 / 3.0).abs() < 0.05, "{:?}", stats[0]);
+        assert!(stats[0].expected_turn.unwrap() <= stats[1].expected_turn.unwrap());
+    }
+}
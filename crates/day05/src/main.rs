@@ -1,109 +1,388 @@
-use std::cmp::{max, min};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::iter::Peekable;
 
 use utils::InputType;
 
 fn main() {
     let xs = read_input(InputType::Input);
     println!("Day05 part a = {}", part_a(&xs, false)); // 8622
-    println!("Day05 part b = {}", part_b(&xs, false)); // 22037
+    println!("Day05 part b = {}", part_b(&xs, Raster::Lattice, false)); // 22037
+
+    // --ppm <file> writes the full field, --ascii x0,y0,x1,y1 prints a viewport
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let arg = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1));
+    let vent_m = process(&xs, |_line| true, Raster::Lattice);
+    if let Some(path) = arg("--ppm") {
+        let view = Viewport::bounds(&xs).unwrap();
+        std::fs::write(path, heatmap_ppm(&vent_m, &view)).unwrap();
+        println!("wrote {:?} heatmap to {}", view, path);
+    }
+    if let Some(view) = arg("--ascii") {
+        match Viewport::parse(view) {
+            Ok(view) => print!("{}", heatmap_ascii(&vent_m, &view)),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    let field = VentField::new(xs, Raster::Lattice, 64);
+    let view = Viewport::bounds(&field.vents).unwrap();
+    let hottest = field.points_above(&view, 1).into_iter().max_by_key(|(point, count)| (*count, Reverse(*point)));
+    if let Some((point, _)) = hottest {
+        println!("most overlaps: {} at {:?}, vents {:?}", field.count_at(point), point, field.vents_at(point));
+    }
+    println!("intersecting vent pairs: {}", field.intersecting_pairs().len());
 }
 
-fn part_a(xs: &Vec<Vent>, show_dump: bool) -> usize {
-    let vent_m: HashMap<(u32, u32), u32> = process_horizontal_and_vertical(xs);
+fn part_a(xs: &[Vent], show_dump: bool) -> usize {
+    let include = |line: &Line| *line == Line::Horizontal || *line == Line::Vertical;
     if show_dump {
-        dump("dump part_a", &vent_m);
+        dump("dump part_a", xs, &process(xs, include, Raster::Lattice));
     }
-    vent_m.values().fold(0, |acc, v| if *v > 1 { acc + 1 } else { acc })
+    count_overlaps(xs, include, Raster::Lattice, 2) as usize
 }
 
-fn part_b(xs: &Vec<Vent>, show_dump: bool) -> usize {
-    let mut vent_m: HashMap<(u32, u32), u32> = process_horizontal_and_vertical(xs);
-    process_diagonal(xs, &mut vent_m);
+fn part_b(xs: &[Vent], raster: Raster, show_dump: bool) -> usize {
     if show_dump {
-        dump("dump part_b", &vent_m);
+        dump("dump part_b", xs, &process(xs, |_line| true, raster));
     }
-    vent_m.values().fold(0, |acc, v| if *v > 1 { acc + 1 } else { acc })
+    count_overlaps(xs, |_line| true, raster, 2) as usize
 }
 
-#[derive(Debug, PartialEq)]
-pub enum Line { Horizontal, Vertical, Diagonal }
+// Diagonal is 45 degrees, Sloped is any other slope
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Line { Horizontal, Vertical, Diagonal, Sloped }
 
+// how cells are picked along a Sloped line, both agree on the other lines.
+// Lattice keeps only the integer points exactly on the segment,
+// Bresenham keeps one cell per step along the major axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Raster { Lattice, Bresenham }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Vent {
     from: (u32, u32),
     to: (u32, u32),
     line: Line,
-    start: usize,
-    end: usize,
 }
 
 impl Vent {
     fn new(xs: &[u32]) -> Vent {
-        let mut line = Line::Diagonal;
-        let mut start = 0;
-        let mut end = 0;
+        assert_eq!(4, xs.len(), "a vent needs x1,y1 -> x2,y2");
+        let (from, to) = ((xs[0], xs[1]), (xs[2], xs[3]));
+        let dx = xs[0].abs_diff(xs[2]);
+        let dy = xs[1].abs_diff(xs[3]);
+        let line = if dx == 0 {
+            Line::Vertical
+        } else if dy == 0 {
+            Line::Horizontal
+        } else if dx == dy {
+            Line::Diagonal
+        } else {
+            Line::Sloped
+        };
+        Vent { from, to, line }
+    }
+
+    // cells run from the endpoint with the smaller (y, x) so y never decreases,
+    // and a vent covers the same cells whichever way round it was written
+    fn cells(&self, raster: Raster) -> Cells {
+        let (from, to) = if (self.from.1, self.from.0) <= (self.to.1, self.to.0) { (self.from, self.to) } else { (self.to, self.from) };
+        let (x0, y0) = (from.0 as i64, from.1 as i64);
+        let (x1, y1) = (to.0 as i64, to.1 as i64);
 
-        if xs.len() != 4 {
-            return Vent { from: (0, 0), to: (0, 0), line, start, end };
+        if raster == Raster::Bresenham && self.line == Line::Sloped {
+            let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+            return Cells::Bresenham { x: x0, y: y0, to: (x1, y1), sx: (x1 - x0).signum(), dx, dy, err: dx + dy, done: false };
         }
 
-        if xs[0] == xs[2] {
-            line = Line::Vertical;
-            start = min(xs[1], xs[3]) as usize;
-            end = max(xs[1], xs[3]) as usize
-        } else if xs[1] == xs[3] {
-            line = Line::Horizontal;
-            start = min(xs[0], xs[2]) as usize;
-            end = max(xs[0], xs[2]) as usize
+        let steps = gcd((x1 - x0).abs(), (y1 - y0).abs());
+        let step = if steps == 0 { (0, 0) } else { ((x1 - x0) / steps, (y1 - y0) / steps) };
+        Cells::Lattice { x: x0, y: y0, step, remaining: steps + 1 }
+    }
+
+    fn covers(&self, point: (u32, u32), raster: Raster) -> bool {
+        let (px, py) = point;
+        if px < self.from.0.min(self.to.0) || px > self.from.0.max(self.to.0)
+            || py < self.from.1.min(self.to.1) || py > self.from.1.max(self.to.1) {
+            return false;
+        }
+        if raster == Raster::Bresenham && self.line == Line::Sloped {
+            return self.cells(raster)
+                .skip_while(|cell| cell.1 < py)
+                .take_while(|cell| cell.1 == py)
+                .any(|cell| cell == point);
+        }
+
+        // on the segment and a whole number of lattice steps from its start
+        let (x0, y0) = (self.from.0 as i64, self.from.1 as i64);
+        let (dx, dy) = (self.to.0 as i64 - x0, self.to.1 as i64 - y0);
+        let (ox, oy) = (px as i64 - x0, py as i64 - y0);
+        let steps = gcd(dx.abs(), dy.abs());
+        if ox * dy != oy * dx {
+            return false;
         }
-        Vent { from: (xs[0], xs[1]), to: (xs[2], xs[3]), line, start, end }
+        steps == 0 || if dx != 0 { ox % (dx / steps) == 0 } else { oy % (dy / steps) == 0 }
     }
 }
 
-fn process_horizontal_and_vertical(vents: &Vec<Vent>) -> HashMap<(u32, u32), u32> {
-    // let mut vent_m: HashMap<String, u32> = HashMap::new();
-    let mut vent_m: HashMap<(u32, u32), u32> = HashMap::new();
+enum Cells {
+    Lattice { x: i64, y: i64, step: (i64, i64), remaining: i64 },
+    Bresenham { x: i64, y: i64, to: (i64, i64), sx: i64, dx: i64, dy: i64, err: i64, done: bool },
+}
+
+impl Iterator for Cells {
+    type Item = (u32, u32);
+
+    fn next(&mut self) -> Option<(u32, u32)> {
+        match self {
+            Cells::Lattice { x, y, step, remaining } => {
+                if *remaining == 0 {
+                    return None;
+                }
+                let cell = (*x as u32, *y as u32);
+                *remaining -= 1;
+                *x += step.0;
+                *y += step.1;
+                Some(cell)
+            }
+            Cells::Bresenham { x, y, to, sx, dx, dy, err, done } => {
+                if *done {
+                    return None;
+                }
+                let cell = (*x as u32, *y as u32);
+                if (*x, *y) == *to {
+                    *done = true;
+                    return Some(cell);
+                }
+                let e2 = 2 * *err;
+                if e2 >= *dy {
+                    *err += *dy;
+                    *x += *sx;
+                }
+                if e2 <= *dx {
+                    *err += *dx;
+                    *y += 1;
+                }
+                Some(cell)
+            }
+        }
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
 
+fn process(vents: &[Vent], include: fn(&Line) -> bool, raster: Raster) -> HashMap<(u32, u32), u32> {
+    let mut vent_m: HashMap<(u32, u32), u32> = HashMap::new();
     vents.iter()
-        .filter(|vent| vent.line != Line::Diagonal)
+        .filter(|vent| include(&vent.line))
         .for_each(|vent| {
-            for i in vent.start..=vent.end {
-                // let key = if vent.line == Line::Horizontal { format!("{}:{}", i, vent.from.1) } else { format!("{}:{}", vent.from.0, i) };
-                let key = if vent.line == Line::Horizontal { (i as u32, vent.from.1) } else { (vent.from.0, i as u32) };
+            for key in vent.cells(raster) {
                 update_map(key, &mut vent_m);
             }
         });
     vent_m
 }
 
-fn process_diagonal(vents: &Vec<Vent>, vent_m: &mut HashMap<(u32, u32), u32>) {
-    vents.iter()
-        .filter(|vent| vent.line == Line::Diagonal)
-        .for_each(|vent| {
-            let mut x = vent.from.0;
-            let mut y = vent.from.1;
-            loop {
-                let key = (x, y);
-                update_map(key, vent_m);
-
-                if x == vent.to.0 || y == vent.to.1 {
-                    break;
+fn update_map(key: (u32, u32), vent_m: &mut HashMap<(u32, u32), u32>) {
+    *vent_m.entry(key).or_insert(0) += 1;
+}
+
+// number of cells covered by at least k vents, sweeping rows from top to bottom.
+// vertical vents live in a column count map while their rows are active, and runs of
+// rows touched by nothing else are counted in one step. horizontal vents and the cells
+// of the other vents, pulled lazily row by row, are merged into each row they touch,
+// so memory stays proportional to the number of vents instead of their length
+fn count_overlaps(vents: &[Vent], include: fn(&Line) -> bool, raster: Raster, k: u32) -> u64 {
+    assert!(k > 0);
+    let mut starts: Vec<&Vent> = vents.iter().filter(|vent| include(&vent.line)).collect();
+    starts.sort_by_key(|vent| vent.from.1.min(vent.to.1));
+    let mut starts = starts.into_iter().peekable();
+
+    let mut columns: BTreeMap<u64, u32> = BTreeMap::new();
+    let mut column_total: u64 = 0;
+    let mut column_ends: BinaryHeap<Reverse<(u64, u64)>> = BinaryHeap::new();
+    let mut sloped: Vec<Peekable<Cells>> = Vec::new();
+    let mut row: Vec<(u64, i64)> = Vec::new();
+    let mut total = 0;
+
+    let mut y = match starts.peek() {
+        Some(vent) => vent.from.1.min(vent.to.1) as u64,
+        None => return 0,
+    };
+    loop {
+        while let Some(Reverse((end, x))) = column_ends.peek().copied() {
+            if end > y {
+                break;
+            }
+            column_ends.pop();
+            let count = columns.get_mut(&x).unwrap();
+            if *count == k {
+                column_total -= 1;
+            }
+            *count -= 1;
+            if *count == 0 {
+                columns.remove(&x);
+            }
+        }
+
+        while let Some(vent) = starts.next_if(|vent| vent.from.1.min(vent.to.1) as u64 == y) {
+            match vent.line {
+                Line::Horizontal => {
+                    row.push((vent.from.0.min(vent.to.0) as u64, 1));
+                    row.push((vent.from.0.max(vent.to.0) as u64 + 1, -1));
+                }
+                Line::Vertical => {
+                    let x = vent.from.0 as u64;
+                    let count = columns.entry(x).or_insert(0);
+                    *count += 1;
+                    if *count == k {
+                        column_total += 1;
+                    }
+                    column_ends.push(Reverse((vent.from.1.max(vent.to.1) as u64 + 1, x)));
                 }
+                _ => sloped.push(vent.cells(raster).peekable()),
+            }
+        }
 
-                if vent.from.0 >= vent.to.0 { x -= 1 } else { x += 1 }
-                if vent.from.1 >= vent.to.1 { y -= 1 } else { y += 1 }
+        for cells in sloped.iter_mut() {
+            while let Some((x, _)) = cells.next_if(|cell| cell.1 as u64 == y) {
+                row.push((x as u64, 1));
+                row.push((x as u64 + 1, -1));
             }
-        })
+        }
+        sloped.retain_mut(|cells| cells.peek().is_some());
+
+        total += count_row(&mut row, &columns, column_total, k);
+        row.clear();
+
+        let next = [
+            starts.peek().map(|vent| vent.from.1.min(vent.to.1) as u64),
+            column_ends.peek().map(|Reverse((end, _))| *end),
+            sloped.iter_mut().filter_map(|cells| cells.peek().map(|cell| cell.1 as u64)).min(),
+        ];
+        let next = match next.iter().flatten().min() {
+            Some(next) => *next,
+            None => return total,
+        };
+        // the rows in between only hold the active columns
+        total += column_total * (next - y - 1);
+        y = next;
+    }
 }
 
-fn update_map(key: (u32, u32), vent_m: &mut HashMap<(u32, u32), u32>) {
-    if let Some(v) = vent_m.get(&key) {
-        let v = *v;
-        vent_m.insert(key, v + 1);
-    } else {
-        vent_m.insert(key, 1);
+// row holds (x, +1) at the start and (x, -1) one past the end of every non vertical run,
+// column_total is the number of columns reaching k from vertical vents alone
+fn count_row(row: &mut [(u64, i64)], columns: &BTreeMap<u64, u32>, column_total: u64, k: u32) -> u64 {
+    row.sort_unstable();
+    let mut total = column_total;
+    let mut covered: i64 = 0;
+    for i in 0..row.len() {
+        covered += row[i].1;
+        let (a, b) = (row[i].0, row.get(i + 1).map_or(row[i].0, |next| next.0));
+        if a == b || covered == 0 {
+            continue;
+        }
+        let c = covered as u32;
+        let in_range = columns.range(a..b);
+        if c >= k {
+            total += (b - a) - in_range.filter(|(_, v)| **v >= k).count() as u64;
+        } else {
+            total += in_range.filter(|(_, v)| **v < k && **v + c >= k).count() as u64;
+        }
+    }
+    total
+}
+
+// vents with their overlap counts, and a grid of square buckets listing the vents
+// and the covered cells that fall inside each bucket
+#[derive(Debug)]
+struct VentField {
+    vents: Vec<Vent>,
+    raster: Raster,
+    bucket_size: u32,
+    counts: HashMap<(u32, u32), u32>,
+    buckets: HashMap<(u32, u32), Bucket>,
+}
+
+#[derive(Debug, Default)]
+struct Bucket {
+    vents: Vec<usize>,
+    cells: Vec<(u32, u32)>,
+}
+
+impl VentField {
+    fn new(vents: Vec<Vent>, raster: Raster, bucket_size: u32) -> VentField {
+        assert!(bucket_size > 0);
+        let counts = process(&vents, |_line| true, raster);
+        let mut buckets: HashMap<(u32, u32), Bucket> = HashMap::new();
+        for (i, vent) in vents.iter().enumerate() {
+            for cell in vent.cells(raster) {
+                let bucket = buckets.entry((cell.0 / bucket_size, cell.1 / bucket_size)).or_default();
+                if bucket.vents.last() != Some(&i) {
+                    bucket.vents.push(i);
+                }
+            }
+        }
+        for cell in counts.keys() {
+            buckets.get_mut(&(cell.0 / bucket_size, cell.1 / bucket_size)).unwrap().cells.push(*cell);
+        }
+        VentField { vents, raster, bucket_size, counts, buckets }
+    }
+
+    fn count_at(&self, point: (u32, u32)) -> u32 {
+        self.counts.get(&point).copied().unwrap_or(0)
+    }
+
+    // indices of the vents covering point, in input order
+    fn vents_at(&self, point: (u32, u32)) -> Vec<usize> {
+        let key = (point.0 / self.bucket_size, point.1 / self.bucket_size);
+        self.buckets.get(&key).map_or(vec![], |bucket| {
+            bucket.vents.iter()
+                .filter(|i| self.vents[**i].covers(point, self.raster))
+                .copied()
+                .collect()
+        })
+    }
+
+    // cells inside view covered by at least k vents, sorted by (x, y). a view spanning more
+    // buckets than are occupied walks the occupied ones instead of every coordinate
+    fn points_above(&self, view: &Viewport, k: u32) -> Vec<((u32, u32), u32)> {
+        let b = self.bucket_size;
+        let (bxs, bys) = (view.x0 / b..=view.x1 / b, view.y0 / b..=view.y1 / b);
+        let spanned = (bxs.end() - bxs.start() + 1) as u64 * (bys.end() - bys.start() + 1) as u64;
+        let keys: Vec<(u32, u32)> = if spanned > self.buckets.len() as u64 {
+            self.buckets.keys().filter(|(bx, by)| bxs.contains(bx) && bys.contains(by)).copied().collect()
+        } else {
+            bxs.flat_map(|bx| bys.clone().map(move |by| (bx, by))).collect()
+        };
+
+        let mut points = Vec::new();
+        for key in keys {
+            let cells = self.buckets.get(&key).map_or(&[][..], |bucket| &bucket.cells[..]);
+            points.extend(cells.iter()
+                .filter(|(x, y)| (view.x0..=view.x1).contains(x) && (view.y0..=view.y1).contains(y))
+                .map(|cell| (*cell, self.counts[cell]))
+                .filter(|(_, count)| *count >= k));
+        }
+        points.sort_unstable();
+        points
+    }
+
+    // pairs (i, j), i < j, of vents sharing at least one cell
+    fn intersecting_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = BTreeSet::new();
+        for (cell, _) in self.counts.iter().filter(|(_, count)| **count > 1) {
+            let vents = self.vents_at(*cell);
+            for (n, i) in vents.iter().enumerate() {
+                for j in &vents[n + 1..] {
+                    pairs.insert((*i, *j));
+                }
+            }
+        }
+        pairs.into_iter().collect()
     }
 }
 
@@ -119,7 +398,7 @@ fn read_input(input_type: InputType) -> Vec<Vent> {
         .lines()
         .map(|s| {
             let xs: Vec<u32> = s.replace(" -> ", ",")
-                .split(",")
+                .split(',')
                 .map(|x| x.parse().unwrap())
                 .collect();
             Vent::new(&xs[0..4])
@@ -129,27 +408,109 @@ fn read_input(input_type: InputType) -> Vec<Vent> {
     vs
 }
 
-fn dump(label: &str, vent_m: &HashMap<(u32, u32), u32>) {
+fn dump(label: &str, vents: &[Vent], vent_m: &HashMap<(u32, u32), u32>) {
     println!("{}", label);
-    for y in 0..=9 {
-        for x in 0..=9 {
-            let key = (x, y);
-            if let Some(v) = vent_m.get(&key) {
-                print!("{}", v)
-            } else {
-                print!(".")
-            }
-        }
-        println!()
+    if let Some(view) = Viewport::bounds(vents) {
+        print!("{}", heatmap_ascii(vent_m, &view));
     }
     println!()
 }
 
+// inclusive rectangle of cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Viewport {
+    x0: u32,
+    y0: u32,
+    x1: u32,
+    y1: u32,
+}
+
+impl Viewport {
+    // smallest viewport holding every vent
+    fn bounds(vents: &[Vent]) -> Option<Viewport> {
+        vents.iter()
+            .map(|vent| Viewport {
+                x0: vent.from.0.min(vent.to.0),
+                y0: vent.from.1.min(vent.to.1),
+                x1: vent.from.0.max(vent.to.0),
+                y1: vent.from.1.max(vent.to.1),
+            })
+            .reduce(|a, b| Viewport { x0: a.x0.min(b.x0), y0: a.y0.min(b.y0), x1: a.x1.max(b.x1), y1: a.y1.max(b.y1) })
+    }
+
+    // "x0,y0,x1,y1"
+    fn parse(s: &str) -> Result<Viewport, String> {
+        let xs = s.split(',')
+            .map(|x| x.trim().parse::<u32>().map_err(|_| format!("invalid viewport {:?}, expected x0,y0,x1,y1", s)))
+            .collect::<Result<Vec<u32>, String>>()?;
+        match xs[..] {
+            [x0, y0, x1, y1] if x0 <= x1 && y0 <= y1 => Ok(Viewport { x0, y0, x1, y1 }),
+            _ => Err(format!("invalid viewport {:?}, expected x0,y0,x1,y1 with x0 <= x1 and y0 <= y1", s)),
+        }
+    }
+
+    fn width(&self) -> usize {
+        (self.x1 - self.x0) as usize + 1
+    }
+
+    fn height(&self) -> usize {
+        (self.y1 - self.y0) as usize + 1
+    }
+}
+
+// '.' for no vent, the count up to 9, '#' above
+fn heatmap_ascii(vent_m: &HashMap<(u32, u32), u32>, view: &Viewport) -> String {
+    let mut s = String::with_capacity((view.width() + 1) * view.height());
+    for y in view.y0..=view.y1 {
+        for x in view.x0..=view.x1 {
+            s.push(match vent_m.get(&(x, y)) {
+                None => '.',
+                Some(v) if *v <= 9 => char::from_digit(*v, 10).unwrap(),
+                Some(_) => '#',
+            });
+        }
+        s.push('\n');
+    }
+    s
+}
+
+// binary PPM, black where there is no vent, then blue through cyan and yellow to red
+// as the count rises towards the highest count in the viewport
+fn heatmap_ppm(vent_m: &HashMap<(u32, u32), u32>, view: &Viewport) -> Vec<u8> {
+    let max = vent_m.iter()
+        .filter(|((x, y), _)| (view.x0..=view.x1).contains(x) && (view.y0..=view.y1).contains(y))
+        .map(|(_, v)| *v)
+        .max()
+        .unwrap_or(1);
+
+    let mut ppm = format!("P6\n{} {}\n255\n", view.width(), view.height()).into_bytes();
+    for y in view.y0..=view.y1 {
+        for x in view.x0..=view.x1 {
+            let rgb = match vent_m.get(&(x, y)) {
+                None => [0, 0, 0],
+                Some(v) => heat_color(if max == 1 { 1.0 } else { (*v - 1) as f64 / (max - 1) as f64 }),
+            };
+            ppm.extend_from_slice(&rgb);
+        }
+    }
+    ppm
+}
+
+fn heat_color(t: f64) -> [u8; 3] {
+    const STOPS: [[f64; 3]; 4] = [[0.0, 0.0, 255.0], [0.0, 255.0, 255.0], [255.0, 255.0, 0.0], [255.0, 0.0, 0.0]];
+    let t = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+    let i = (t as usize).min(STOPS.len() - 2);
+    let f = t - i as f64;
+    let mix = |c: usize| (STOPS[i][c] + (STOPS[i + 1][c] - STOPS[i][c]) * f).round() as u8;
+    [mix(0), mix(1), mix(2)]
+}
+
 #[cfg(test)]
 mod tests {
     use utils::InputType;
 
-    use crate::{part_a, part_b, read_input};
+    use crate::{count_overlaps, heat_color, heatmap_ascii, heatmap_ppm, part_a, part_b, process, read_input, Line, Raster, Vent, VentField, Viewport};
+    use utils::Rng;
 
     #[test]
     fn test_part_a() {
@@ -160,6 +521,136 @@ mod tests {
     #[test]
     fn test_part_b() {
         let xs = read_input(InputType::Sample);
-        assert_eq!(12, part_b(&xs, true));
+        assert_eq!(12, part_b(&xs, Raster::Lattice, true));
+        assert_eq!(12, part_b(&xs, Raster::Bresenham, false));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_sloped() {
+        let vent = Vent::new(&[0, 0, 6, 3]);
+        assert_eq!(Line::Sloped, vent.line);
+        assert_eq!(vec![(0, 0), (2, 1), (4, 2), (6, 3)], vent.cells(Raster::Lattice).collect::<Vec<_>>());
+        assert_eq!(vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2), (5, 3), (6, 3)], vent.cells(Raster::Bresenham).collect::<Vec<_>>());
+
+        let vent = Vent::new(&[4, 4, 5, 1]);
+        assert_eq!(vec![(5, 1), (4, 4)], vent.cells(Raster::Lattice).collect::<Vec<_>>());
+        assert_eq!(vec![(5, 1), (5, 2), (4, 3), (4, 4)], vent.cells(Raster::Bresenham).collect::<Vec<_>>());
+
+        let vent = Vent::new(&[8, 0, 0, 8]);
+        assert_eq!(Line::Diagonal, vent.line);
+        assert!(vent.cells(Raster::Lattice).eq(vent.cells(Raster::Bresenham)));
+        assert_eq!(Some((0, 8)), vent.cells(Raster::Lattice).last());
+
+        assert_eq!(vec![(3, 3)], Vent::new(&[3, 3, 3, 3]).cells(Raster::Bresenham).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sweep_matches_map() {
+        let xs = read_input(InputType::Sample);
+        let mut rng = Rng::new(5);
+        let random: Vec<Vent> = (0..300)
+            .map(|i| {
+                let mut xs: Vec<u32> = (0..4).map(|_| rng.below(40) as u32).collect();
+                match i % 4 {
+                    0 => xs[2] = xs[0],
+                    1 => xs[3] = xs[1],
+                    _ => (),
+                }
+                Vent::new(&xs)
+            })
+            .collect();
+
+        for vents in [&xs, &random] {
+            for raster in [Raster::Lattice, Raster::Bresenham] {
+                let vent_m = process(vents, |_line| true, raster);
+                for k in 1..=4 {
+                    let expected = vent_m.values().filter(|v| **v >= k).count() as u64;
+                    assert_eq!(expected, count_overlaps(vents, |_line| true, raster, k), "{:?}, k = {}", raster, k);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sweep_large_coordinates() {
+        let vents = vec![
+            Vent::new(&[5_000_000, 0, 5_000_000, 3_000_000]),
+            Vent::new(&[5_000_000, 1_000_000, 5_000_000, 4_000_000]),
+            Vent::new(&[0, 2_000_000, 9_000_000, 2_000_000]),
+            Vent::new(&[4_900_000, 1_900_000, 5_100_000, 2_100_000]),
+        ];
+        // the two verticals share 2_000_001 rows, all four vents meet at (5_000_000, 2_000_000)
+        assert_eq!(2_000_001, count_overlaps(&vents, |_line| true, Raster::Lattice, 2));
+        assert_eq!(1, count_overlaps(&vents, |_line| true, Raster::Lattice, 4));
+        assert_eq!(4_000_001 + 9_000_001 + 200_001 - 2,
+                   count_overlaps(&vents, |_line| true, Raster::Lattice, 1));
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let xs = read_input(InputType::Sample);
+        let view = Viewport::bounds(&xs).unwrap();
+        assert_eq!(Viewport { x0: 0, y0: 0, x1: 9, y1: 9 }, view);
+
+        let vent_m = process(&xs, |_line| true, Raster::Lattice);
+        let ascii = heatmap_ascii(&vent_m, &view);
+        assert_eq!("1.1....11.", ascii.lines().next().unwrap());
+        assert_eq!("222111....", ascii.lines().last().unwrap());
+        assert_eq!(".11.\n", heatmap_ascii(&vent_m, &Viewport::parse("6,0,9,0").unwrap()));
+
+        let ppm = heatmap_ppm(&vent_m, &view);
+        let header = "P6\n10 10\n255\n";
+        assert!(ppm.starts_with(header.as_bytes()));
+        assert_eq!(header.len() + 300, ppm.len());
+        assert_eq!([0, 0, 255], ppm[header.len()..header.len() + 3]);
+        assert_eq!([0, 0, 0], ppm[header.len() + 3..header.len() + 6]);
+
+        assert_eq!([255, 0, 0], heat_color(1.0));
+        assert_eq!([0, 255, 255], heat_color(1.0 / 3.0));
+        assert!(Viewport::parse("1,2,3").is_err());
+        assert!(Viewport::parse("3,0,1,0").is_err());
+    }
+
+    #[test]
+    fn test_vent_field() {
+        let xs = read_input(InputType::Sample);
+        let field = VentField::new(xs.clone(), Raster::Lattice, 4);
+        assert_eq!(3, field.count_at((4, 4)));
+        assert_eq!(0, field.count_at((0, 1)));
+        assert_eq!(vec![1, 2, 8], field.vents_at((4, 4)));
+        assert!(field.vents_at((50, 50)).is_empty());
+
+        let view = Viewport::parse("0,0,9,9").unwrap();
+        assert_eq!(12, field.points_above(&view, 2).len());
+        assert_eq!(vec![((4, 4), 3), ((6, 4), 3)], field.points_above(&view, 3));
+        assert_eq!(vec![((0, 9), 2), ((1, 9), 2)], field.points_above(&Viewport::parse("0,9,1,9").unwrap(), 2));
+
+        // a view far larger than the field only touches the occupied buckets
+        let huge = Viewport::parse("0,0,5000000,5000000").unwrap();
+        assert_eq!(field.points_above(&view, 2), field.points_above(&huge, 2));
+        assert_eq!(vec![((4, 4), 3), ((6, 4), 3)], field.points_above(&Viewport::parse("4,0,4000000,4").unwrap(), 3));
+
+        let pairs = field.intersecting_pairs();
+        assert!(pairs.contains(&(0, 6)));
+        assert!(pairs.contains(&(2, 8)));
+        assert!(!pairs.contains(&(0, 1)));
+
+        // every query agrees with walking the cells of every vent
+        let all: Vec<((u32, u32), u32)> = {
+            let mut all: Vec<_> = process(&xs, |_line| true, Raster::Lattice).into_iter().collect();
+            all.sort_unstable();
+            all
+        };
+        assert_eq!(all, field.points_above(&view, 1));
+        for raster in [Raster::Lattice, Raster::Bresenham] {
+            let vents = vec![Vent::new(&[0, 0, 6, 3]), Vent::new(&[2, 0, 2, 5]), Vent::new(&[0, 3, 6, 0])];
+            let field = VentField::new(vents.clone(), raster, 2);
+            for x in 0..8 {
+                for y in 0..8 {
+                    let expected: Vec<usize> = (0..vents.len()).filter(|i| vents[*i].cells(raster).any(|c| c == (x, y))).collect();
+                    assert_eq!(expected, field.vents_at((x, y)), "{:?} at {:?}", raster, (x, y));
+                }
+            }
+        }
+    }
+}
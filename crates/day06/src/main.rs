@@ -1,35 +1,94 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
 use utils::InputType;
 
 fn main() {
     let xs = read_input(InputType::Input);
     println!("Day06 part a = {}", part_a(&xs, 80)); // 352872
     println!("Day06 part b = {}", part_b(&xs, 256)); // 1604361182149
+
+    let model = LifecycleModel { lifespan: Some(40), ..LifecycleModel::default() };
+    let acc = Accumulator::new(model, &xs).unwrap();
+    println!("{:?} after 256 days = {}", model, acc.into_iter().nth(255).unwrap().total());
+
+    println!("[matrix] Day06 part b = {}", population(LifecycleModel::default(), &xs, 256).unwrap());
+    println!("[matrix] after 10^18 days mod 1_000_000_007 = {}",
+             population_mod(LifecycleModel::default(), &xs, 1_000_000_000_000_000_000, 1_000_000_007).unwrap());
+
+    println!("growth rate per day = {:.6}", growth_rate(&LifecycleModel::default()));
+    // --csv <file> writes the population for days 0..=256
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(path) = args.iter().position(|arg| arg == "--csv").and_then(|i| args.get(i + 1)) {
+        std::fs::write(path, series(LifecycleModel::default(), &xs, 0..=256).unwrap().csv()).unwrap();
+        println!("wrote days 0..=256 to {}", path);
+    }
+}
+
+// a fish gives birth every cycle days, a newborn waits newborn_delay days longer before
+// its first birth, and dies lifespan days after it was born. fish in the initial state
+// count as born on day 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct LifecycleModel {
+    cycle: usize,
+    newborn_delay: usize,
+    litter: usize,
+    lifespan: Option<usize>,
+}
+
+impl Default for LifecycleModel {
+    fn default() -> LifecycleModel {
+        LifecycleModel { cycle: 7, newborn_delay: 2, litter: 1, lifespan: None }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl LifecycleModel {
+    fn reset_timer(&self) -> usize {
+        self.cycle - 1
+    }
+
+    fn newborn_timer(&self) -> usize {
+        self.cycle - 1 + self.newborn_delay
+    }
+
+    fn timers(&self) -> usize {
+        self.newborn_timer() + 1
+    }
+
+    // one bin per timer, or per (age, timer) when fish die of old age
+    fn bins(&self) -> usize {
+        self.timers() * self.lifespan.unwrap_or(1)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.cycle == 0 {
+            return Err("cycle must be at least 1 day".to_string());
+        }
+        if self.lifespan == Some(0) {
+            return Err("lifespan must be at least 1 day".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Accumulator {
-    bin: [usize; 9],
+    model: LifecycleModel,
+    bin: Vec<usize>,
 }
 
 impl Accumulator {
-    fn new(xs: &Vec<u32>) -> Accumulator {
-        let mut bin = [0; 9];
+    fn new(model: LifecycleModel, xs: &[u32]) -> Result<Accumulator, String> {
+        model.validate()?;
+        let mut bin = vec![0; model.bins()];
         for x in xs {
             let i = *x as usize;
-            match i {
-                0 => bin[i] += 1,
-                1 => bin[i] += 1,
-                2 => bin[i] += 1,
-                3 => bin[i] += 1,
-                4 => bin[i] += 1,
-                5 => bin[i] += 1,
-                6 => bin[i] += 1,
-                7 => bin[i] += 1,
-                8 => bin[i] += 1,
-                _ => panic!("unreachable, x = {}", x)
+            if i > model.newborn_timer() {
+                return Err(format!("timer {} is above the newborn timer {}", x, model.newborn_timer()));
             }
+            bin[i] += 1;
         }
-        Accumulator { bin }
+        Ok(Accumulator { model, bin })
     }
 
     fn total(&self) -> usize {
@@ -41,40 +100,324 @@ impl Iterator for Accumulator {
     type Item = Accumulator;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let zeros = self.bin[0];
-        self.bin.rotate_left(1);
-        self.bin[6] += zeros;
-        Some(*self)
-    }
-}
-
-fn part_a(xs: &Vec<u32>, days: u32) -> usize {
-    let mut count = 1;
-    let mut ys = xs.clone();
-    // println!("Initial state: {:?}", ys);
-    while count <= days {
-        let zeros: usize = ys.iter().fold(0, |acc, y| if *y == 0 { acc + 1 } else { acc });
-        ys = ys.iter().map(|y| {
-            match y {
-                1..=8 => *y - 1u32,
-                0 => 6,
-                _ => panic!("should not be here, y = {}", y)
+        self.bin = step(&self.model, &self.bin);
+        Some(self.clone())
+    }
+}
+
+fn step(m: &LifecycleModel, bin: &[usize]) -> Vec<usize> {
+    let timers = m.timers();
+    let mut next = vec![0; bin.len()];
+    let mut births = 0;
+    for (i, count) in bin.iter().enumerate() {
+        let (age, timer) = (i / timers, i % timers);
+        let timer = if timer == 0 {
+            births += count * m.litter;
+            m.reset_timer()
+        } else {
+            timer - 1
+        };
+        if m.lifespan.is_none_or(|lifespan| age + 1 < lifespan) {
+            let age = if m.lifespan.is_some() { age + 1 } else { 0 };
+            next[age * timers + timer] += count;
+        }
+    }
+    next[m.newborn_timer()] += births;
+    next
+}
+
+// one day as a matrix, next bins = transition * bins. column j is the step of a lone fish in bin j
+fn transition(model: &LifecycleModel) -> Vec<Vec<u64>> {
+    let n = model.bins();
+    let mut matrix = vec![vec![0; n]; n];
+    for j in 0..n {
+        let mut unit = vec![0; n];
+        unit[j] = 1;
+        for (i, v) in step(model, &unit).into_iter().enumerate() {
+            matrix[i][j] = v as u64;
+        }
+    }
+    matrix
+}
+
+trait Scalar: Clone {
+    fn add(&self, other: &Self) -> Self;
+    fn mul(&self, other: &Self) -> Self;
+}
+
+// integers modulo m, m < 2^64
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ModInt {
+    v: u64,
+    m: u64,
+}
+
+impl Scalar for ModInt {
+    fn add(&self, other: &ModInt) -> ModInt {
+        ModInt { v: ((self.v as u128 + other.v as u128) % self.m as u128) as u64, m: self.m }
+    }
+
+    fn mul(&self, other: &ModInt) -> ModInt {
+        ModInt { v: ((self.v as u128 * other.v as u128) % self.m as u128) as u64, m: self.m }
+    }
+}
+
+// arbitrary precision unsigned integer, little endian base 2^32 limbs without trailing zeros
+#[derive(Debug, Clone, PartialEq)]
+struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    fn from_u64(v: u64) -> BigUint {
+        let mut limbs = vec![v as u32, (v >> 32) as u32];
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+
+    fn div_rem_u32(&self, d: u32) -> (BigUint, u32) {
+        let mut limbs = vec![0; self.limbs.len()];
+        let mut r = 0u64;
+        for i in (0..self.limbs.len()).rev() {
+            let cur = (r << 32) | self.limbs[i] as u64;
+            limbs[i] = (cur / d as u64) as u32;
+            r = cur % d as u64;
+        }
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        (BigUint { limbs }, r as u32)
+    }
+}
+
+impl Scalar for BigUint {
+    fn add(&self, other: &BigUint) -> BigUint {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0u64;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64 + *other.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        BigUint { limbs }
+    }
+
+    fn mul(&self, other: &BigUint) -> BigUint {
+        if self.limbs.is_empty() || other.limbs.is_empty() {
+            return BigUint { limbs: vec![] };
+        }
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let cur = limbs[i + j] as u64 + *a as u64 * *b as u64 + carry;
+                limbs[i + j] = cur as u32;
+                carry = cur >> 32;
             }
-        }).collect::<Vec<u32>>();
-        let mut zs: Vec<u32> = (0..zeros).map(|_i| 8u32).collect();
-        ys.append(&mut zs);
-        // println!("after {:>2} days: {:?}", count, ys);
-        count += 1;
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        BigUint { limbs }
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // base 10^9 chunks, least significant first
+        let mut chunks = Vec::new();
+        let mut n = self.clone();
+        while !n.limbs.is_empty() {
+            let (q, r) = n.div_rem_u32(1_000_000_000);
+            chunks.push(r);
+            n = q;
+        }
+        match chunks.split_last() {
+            None => write!(f, "0"),
+            Some((first, rest)) => {
+                write!(f, "{}", first)?;
+                rest.iter().rev().try_for_each(|chunk| write!(f, "{:09}", chunk))
+            }
+        }
     }
-    ys.len()
 }
 
-fn part_b(xs: &Vec<u32>, days: usize) -> usize {
-    let acc = Accumulator::new(xs);
+fn matrix_mul<T: Scalar>(a: &[Vec<T>], b: &[Vec<T>], zero: &T) -> Vec<Vec<T>> {
+    let n = a.len();
+    (0..n)
+        .map(|i| (0..n)
+            .map(|j| (0..n).fold(zero.clone(), |acc, k| acc.add(&a[i][k].mul(&b[k][j]))))
+            .collect())
+        .collect()
+}
+
+// repeated squaring, O(bins^3 * log days) scalar operations
+fn matrix_pow<T: Scalar>(m: &[Vec<T>], mut e: u64, zero: &T, one: &T) -> Vec<Vec<T>> {
+    let n = m.len();
+    let mut result: Vec<Vec<T>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { one.clone() } else { zero.clone() }).collect())
+        .collect();
+    let mut base = m.to_vec();
+    while e > 0 {
+        if e & 1 == 1 {
+            result = matrix_mul(&result, &base, zero);
+        }
+        e >>= 1;
+        if e > 0 {
+            base = matrix_mul(&base, &base, zero);
+        }
+    }
+    result
+}
+
+fn project<T: Scalar>(model: LifecycleModel, xs: &[u32], days: u64, lift: impl Fn(u64) -> T) -> Result<T, String> {
+    let bins = Accumulator::new(model, xs)?.bin;
+    let (zero, one) = (lift(0), lift(1));
+    let matrix: Vec<Vec<T>> = transition(&model)
+        .iter()
+        .map(|row| row.iter().map(|v| lift(*v)).collect())
+        .collect();
+    let power = matrix_pow(&matrix, days, &zero, &one);
+    let total = power.iter()
+        .flat_map(|row| row.iter().zip(&bins))
+        .fold(zero, |acc, (v, count)| acc.add(&v.mul(&lift(*count as u64))));
+    Ok(total)
+}
+
+// exact population after days, the result grows by about days / 8 bits with the default model
+fn population(model: LifecycleModel, xs: &[u32], days: u64) -> Result<BigUint, String> {
+    project(model, xs, days, BigUint::from_u64)
+}
+
+// population after days modulo m, typically a large prime, fast for any days up to 10^18 and beyond
+fn population_mod(model: LifecycleModel, xs: &[u32], days: u64, m: u64) -> Result<u64, String> {
+    if m < 2 {
+        return Err(format!("modulus must be at least 2, got {}", m));
+    }
+    project(model, xs, days, |v| ModInt { v: v % m, m }).map(|total| total.v)
+}
+
+// bins for each day in a range, day 0 is the initial state
+#[derive(Debug, Clone)]
+struct Series {
+    model: LifecycleModel,
+    days: Vec<(usize, Vec<BigUint>)>,
+}
+
+impl Series {
+    fn totals(&self) -> Vec<(usize, BigUint)> {
+        self.days
+            .iter()
+            .map(|(day, bin)| (*day, bin.iter().fold(BigUint::from_u64(0), |acc, v| acc.add(v))))
+            .collect()
+    }
+
+    fn csv(&self) -> String {
+        let timers = self.model.timers();
+        let labels: Vec<String> = (0..self.model.bins())
+            .map(|i| match self.model.lifespan {
+                Some(_) => format!("age{}_timer{}", i / timers, i % timers),
+                None => format!("timer{}", i),
+            })
+            .collect();
+        let mut s = format!("day,{},total\n", labels.join(","));
+        for ((day, bin), (_, total)) in self.days.iter().zip(self.totals()) {
+            let counts: Vec<String> = bin.iter().map(|v| v.to_string()).collect();
+            s += &format!("{},{},{}\n", day, counts.join(","), total);
+        }
+        s
+    }
+}
+
+// counts are exact, the default model outgrows usize around day 490
+fn series(model: LifecycleModel, xs: &[u32], days: RangeInclusive<usize>) -> Result<Series, String> {
+    let matrix = transition(&model);
+    let mut bin: Vec<BigUint> = Accumulator::new(model, xs)?.bin
+        .iter()
+        .map(|v| BigUint::from_u64(*v as u64))
+        .collect();
+    let mut ys = Vec::with_capacity(days.end().saturating_sub(*days.start()) + 1);
+    for day in 0..=*days.end() {
+        if days.contains(&day) {
+            ys.push((day, bin.clone()));
+        }
+        bin = matrix
+            .iter()
+            .map(|row| row.iter()
+                .zip(&bin)
+                .filter(|(v, _)| **v > 0)
+                .fold(BigUint::from_u64(0), |acc, (v, count)| acc.add(&BigUint::from_u64(*v).mul(count))))
+            .collect();
+    }
+    Ok(Series { model, days: ys })
+}
+
+// a newborn's litters arrive cycle + newborn_delay days after its birth and every cycle days after
+// that, up to and including the day it dies. offspring(rate) is the number of offspring of one
+// newborn, each discounted by rate^-age
+fn offspring(model: &LifecycleModel, rate: f64) -> f64 {
+    let first = (model.cycle + model.newborn_delay) as f64;
+    let cycle = model.cycle as f64;
+    let litter = model.litter as f64;
+    match model.lifespan {
+        // geometric series, only converges for rate > 1
+        None => litter * rate.powf(-first) / (1.0 - rate.powf(-cycle)),
+        Some(lifespan) => (model.cycle + model.newborn_delay..=lifespan)
+            .step_by(model.cycle)
+            .map(|age| litter * rate.powf(-(age as f64)))
+            .sum(),
+    }
+}
+
+// dominant eigenvalue of the transition, the long run factor the population grows by each day.
+// it is the root of the renewal equation offspring(rate) = 1, found by bisection. offspring is
+// decreasing in rate and at most 1 once rate reaches litter + 1
+fn growth_rate(model: &LifecycleModel) -> f64 {
+    if model.litter == 0 || model.lifespan.is_some_and(|lifespan| lifespan < model.cycle + model.newborn_delay) {
+        return 0.0;
+    }
+    let (mut lo, mut hi) = (if model.lifespan.is_none() { 1.0 } else { 0.0 }, model.litter as f64 + 1.0);
+    loop {
+        let mid = (lo + hi) / 2.0;
+        if mid <= lo || mid >= hi {
+            return mid;
+        }
+        if offspring(model, mid) > 1.0 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+}
+
+fn part_a(xs: &[u32], days: u32) -> usize {
+    count_fish(LifecycleModel::default(), xs, days)
+}
+
+fn part_b(xs: &[u32], days: usize) -> usize {
+    let acc = Accumulator::new(LifecycleModel::default(), xs).unwrap();
     let ys = acc.into_iter().nth(days - 1).unwrap();
-    let total = ys.total();
-    // println!("total: {}, {:?}", total, ys);
-    total
+    ys.total()
+}
+
+// simulates every fish, (timer, age)
+fn count_fish(model: LifecycleModel, xs: &[u32], days: u32) -> usize {
+    let mut ys: Vec<(usize, usize)> = xs.iter().map(|x| (*x as usize, 0)).collect();
+    for _ in 0..days {
+        let births: usize = ys.iter().filter(|(timer, _)| *timer == 0).count() * model.litter;
+        ys = ys.iter()
+            .map(|(timer, age)| (if *timer == 0 { model.reset_timer() } else { timer - 1 }, age + 1))
+            .filter(|(_, age)| model.lifespan.is_none_or(|lifespan| *age < lifespan))
+            .collect();
+        ys.extend((0..births).map(|_| (model.newborn_timer(), 0)));
+    }
+    ys.len()
 }
 
 fn read_input(input_type: InputType) -> Vec<u32> {
@@ -85,15 +428,15 @@ fn read_input(input_type: InputType) -> Vec<u32> {
         }
     };
 
-    data.split(",")
-        .map(|x| x.parse().unwrap())
+    data.split(',')
+        .map(|x| x.trim().parse().unwrap())
         .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use utils::InputType;
-    use crate::{read_input, part_a, part_b};
+    use crate::{count_fish, growth_rate, series, population, population_mod, read_input, part_a, part_b, Accumulator, BigUint, LifecycleModel, Scalar};
 
     #[test]
     fn test_part_a() {
@@ -109,4 +452,124 @@ mod tests {
         assert_eq!(5934, part_b(&xs, 80));
         assert_eq!(26984457539, part_b(&xs, 256));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_lifecycle_models() {
+        let xs = read_input(InputType::Sample);
+        let models = [
+            LifecycleModel::default(),
+            LifecycleModel { cycle: 5, newborn_delay: 0, litter: 1, lifespan: None },
+            LifecycleModel { cycle: 7, newborn_delay: 2, litter: 3, lifespan: None },
+            LifecycleModel { cycle: 5, newborn_delay: 4, litter: 2, lifespan: Some(12) },
+            LifecycleModel { cycle: 7, newborn_delay: 2, litter: 1, lifespan: Some(1) },
+        ];
+        for model in models {
+            let acc = Accumulator::new(model, &xs).unwrap();
+            for (day, ys) in acc.into_iter().take(40).enumerate() {
+                assert_eq!(count_fish(model, &xs, day as u32 + 1), ys.total(), "{:?} day {}", model, day + 1);
+            }
+        }
+
+        // with a one day lifespan every fish is gone after the first day
+        let model = LifecycleModel { lifespan: Some(1), ..LifecycleModel::default() };
+        assert_eq!(0, Accumulator::new(model, &xs).unwrap().next().unwrap().total());
+    }
+
+    #[test]
+    fn test_invalid_timers() {
+        let model = LifecycleModel::default();
+        assert!(Accumulator::new(model, &[3, 9]).is_err());
+        assert!(Accumulator::new(LifecycleModel { cycle: 3, newborn_delay: 1, ..model }, &[3, 4]).is_err());
+        assert!(Accumulator::new(LifecycleModel { cycle: 0, ..model }, &[0]).is_err());
+        assert!(Accumulator::new(LifecycleModel { lifespan: Some(0), ..model }, &[0]).is_err());
+    }
+
+    #[test]
+    fn test_matrix_projection() {
+        let xs = read_input(InputType::Sample);
+        let model = LifecycleModel::default();
+        for days in [18, 80, 256] {
+            let expected = part_b(&xs, days as usize);
+            assert_eq!(expected.to_string(), population(model, &xs, days).unwrap().to_string());
+            assert_eq!(expected as u64 % 1_000_000_007, population_mod(model, &xs, days, 1_000_000_007).unwrap());
+        }
+        assert_eq!("5", population(model, &xs, 0).unwrap().to_string());
+
+        let model = LifecycleModel { cycle: 5, newborn_delay: 4, litter: 2, lifespan: Some(6) };
+        let acc = Accumulator::new(model, &xs).unwrap();
+        assert_eq!(acc.into_iter().nth(99).unwrap().total().to_string(), population(model, &xs, 100).unwrap().to_string());
+
+        // exact and modular agree far beyond u64
+        let exact = population(LifecycleModel::default(), &xs, 2000).unwrap();
+        assert!(exact.to_string().len() > 70);
+        let rem = exact.limbs.iter().rev().fold(0u64, |r, limb| (((r as u128) << 32 | *limb as u128) % 998_244_353) as u64);
+        assert_eq!(rem, population_mod(LifecycleModel::default(), &xs, 2000, 998_244_353).unwrap());
+
+        assert!(population_mod(LifecycleModel::default(), &xs, 1_000_000_000_000_000_000, 1_000_000_007).unwrap() < 1_000_000_007);
+        assert!(population_mod(LifecycleModel::default(), &xs, 10, 1).is_err());
+    }
+
+    #[test]
+    fn test_big_uint() {
+        let a = BigUint::from_u64(u64::MAX);
+        assert_eq!("18446744073709551615", a.to_string());
+        assert_eq!("36893488147419103230", a.add(&a).to_string());
+        assert_eq!("340282366920938463426481119284349108225", a.mul(&a).to_string());
+        assert_eq!("0", BigUint::from_u64(0).to_string());
+        assert_eq!("1000000000", BigUint::from_u64(1_000_000_000).to_string());
+    }
+
+    #[test]
+    fn test_series() {
+        let xs = read_input(InputType::Sample);
+        let model = LifecycleModel::default();
+        let big = BigUint::from_u64;
+        let ys = series(model, &xs, 0..=80).unwrap();
+        assert_eq!(81, ys.days.len());
+        assert_eq!((0, big(5)), ys.totals()[0]);
+        assert_eq!((18, big(26)), ys.totals()[18]);
+        assert_eq!((80, big(5934)), ys.totals()[80]);
+        assert_eq!(vec![0, 1, 1, 2, 1, 0, 0, 0, 0].into_iter().map(big).collect::<Vec<_>>(), ys.days[0].1);
+
+        let ys = series(model, &xs, 17..=18).unwrap();
+        let csv = ys.csv();
+        assert_eq!("day,timer0,timer1,timer2,timer3,timer4,timer5,timer6,timer7,timer8,total", csv.lines().next().unwrap());
+        assert_eq!(3, csv.lines().count());
+        assert!(csv.ends_with(",26\n"));
+
+        let aged = LifecycleModel { lifespan: Some(2), ..model };
+        assert!(series(aged, &xs, 0..=0).unwrap().csv().starts_with("day,age0_timer0,"));
+        assert!(series(model, &[9], 0..=1).is_err());
+
+        // past usize, the counts keep matching the matrix projection
+        let ys = series(model, &xs, 255..=600).unwrap();
+        assert_eq!((256, big(26984457539)), ys.totals()[1]);
+        for (day, total) in ys.totals().into_iter().step_by(50) {
+            assert_eq!(population(model, &xs, day as u64).unwrap(), total, "day {}", day);
+        }
+        assert!(ys.totals()[345].1.to_string().len() > 20);
+        assert!(ys.csv().lines().last().unwrap().starts_with("600,"));
+    }
+
+    #[test]
+    fn test_growth_rate() {
+        let rate = growth_rate(&LifecycleModel::default());
+        // the characteristic polynomial of the default lifecycle is x^9 - x^2 - 1
+        assert!((rate.powi(9) - rate.powi(2) - 1.0).abs() < 1e-9, "{}", rate);
+
+        // every fish splits in two each day
+        let doubling = LifecycleModel { cycle: 1, newborn_delay: 0, litter: 1, lifespan: None };
+        assert!((growth_rate(&doubling) - 2.0).abs() < 1e-12);
+        // a litter of 7 every 3 days, the fish stay in step so the daily factor never settles
+        let periodic = LifecycleModel { cycle: 3, newborn_delay: 0, litter: 7, lifespan: None };
+        assert!((growth_rate(&periodic) - 2.0).abs() < 1e-12);
+
+        // a single litter at day 9 only replaces the parent
+        assert_eq!(1.0, growth_rate(&LifecycleModel { lifespan: Some(9), ..LifecycleModel::default() }));
+        // litters at days 9 and 16, so x^16 - x^7 - 1 = 0
+        let rate = growth_rate(&LifecycleModel { lifespan: Some(16), ..LifecycleModel::default() });
+        assert!((rate.powi(16) - rate.powi(7) - 1.0).abs() < 1e-9, "{}", rate);
+
+        assert_eq!(0.0, growth_rate(&LifecycleModel { lifespan: Some(3), ..LifecycleModel::default() }));
+    }
+}
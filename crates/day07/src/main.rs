@@ -1,49 +1,299 @@
+use std::ops::RangeInclusive;
 use utils::InputType;
-use std::collections::HashMap;
 
 fn main() {
     let xs = read_input(InputType::Input);
     println!("Day07 part a = {}", part_a(&xs)); // 348664
-    println!("Day07 part b = {}", part_b(&xs));
+    println!("Day07 part b = {}", part_b(&xs)); // 100220525
+
+    let a = align_linear(&xs);
+    let b = align_triangular(&xs);
+    println!("linear: position {}, fuel {}", a.position, a.fuel);
+    println!("triangular: position {}, fuel {}", b.position, b.fuel);
+    println!("quadratic: {:?}", align_convex(&xs, Cost::Quadratic));
+
+    // crabs sharing a position, grouped into one weighted crab
+    let mut counts = std::collections::BTreeMap::new();
+    xs.iter().for_each(|x| *counts.entry(*x).or_insert(0) += 1);
+    let grouped = Crabs::weighted(counts.keys().copied().collect(), counts.values().copied().collect()).unwrap();
+    for cost in [Cost::Linear, Cost::Triangular, Cost::Quadratic, Cost::Exponential] {
+        match grouped.align(&cost) {
+            a if a.fuel == usize::MAX => println!("{:?}: fuel overflows", cost),
+            a => println!("{:?}: position {}, fuel {}", cost, a.position, a.fuel),
+        }
+    }
+
+    let clusters = grouped.cluster(&Cost::Triangular, 3).unwrap();
+    println!("triangular, 3 meeting points {:?}: fuel {}", clusters.centers, clusters.fuel); // [147, 614, 1275]: 11747546
+
+    let curve = Crabs::new(xs).curve(&Cost::Triangular);
+    let flat = curve.within(curve.best().fuel / 100);
+    println!("triangular within 1% of optimum: positions {}..={}", flat.start(), flat.end());
+}
+
+fn part_a(xs: &[usize]) -> usize {
+    align_linear(xs).fuel
 }
 
-fn part_a(xs: &Vec<usize>) -> usize {
-    let max = *xs.iter().max().unwrap() as usize;
-    let min = *xs.iter().min().unwrap() as usize;
-    let ys: Vec<usize> = (min..=max).map(|v| distance_a(&v, xs)).collect();
-    let res = *ys.iter().min().unwrap();
+fn part_b(xs: &[usize]) -> usize {
+    align_triangular(xs).fuel
+}
 
-    // let zipped: Vec<(&usize, usize)> = xs.iter().zip(ys).collect();
-    // println!("{:?}", zipped);
-    res
+// the smallest best position and the fuel spent to get there
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Alignment {
+    position: usize,
+    fuel: usize,
 }
 
-fn part_b(xs: &Vec<usize>) -> usize {
-    let max = *xs.iter().max().unwrap() as usize;
-    let min = *xs.iter().min().unwrap() as usize;
-    let delta = max - min;
-    let cache: HashMap<usize, usize> = (0..=delta).map(|v| (v, (0..=v).sum())).collect();
-    let ys: Vec<usize> = (min..=max).map(|v| distance_b(&v, xs, &cache)).collect();
-    let res = *ys.iter().min().unwrap();
+// fuel totals saturate at usize::MAX, the exact totals in u128 decide between positions
+fn distance_a(destination: &usize, xs: &[usize]) -> usize {
+    clip(exact_distance_a(destination, xs))
+}
 
-    // let zipped: Vec<(&usize, usize)> = xs.iter().zip(ys).collect();
-    // println!("{:?}", zipped);
-    res
+fn distance_b(destination: &usize, xs: &[usize]) -> usize {
+    clip(exact_distance_b(destination, xs))
 }
 
-fn distance_a(destination: &usize, xs: &Vec<usize>) -> usize {
-    xs.iter().fold(0, |acc, x| if x <= destination { acc + destination - x } else { acc + x - destination })
+fn exact_distance_a(destination: &usize, xs: &[usize]) -> u128 {
+    xs.iter().fold(0, |acc, x| acc + destination.abs_diff(*x) as u128)
 }
 
-fn distance_b(destination: &usize, xs: &Vec<usize>, cache: &HashMap<usize, usize>) -> usize {
-    xs.iter().fold(0, |acc, x|
-        if x <= destination {
-            let delta = destination - x;
-            acc + cache.get(&delta).unwrap()
-        } else {
-            let delta = x - destination;
-            acc + cache.get(&delta).unwrap()
-        })
+fn exact_distance_b(destination: &usize, xs: &[usize]) -> u128 {
+    xs.iter().fold(0, |acc, x| {
+        let delta = destination.abs_diff(*x) as u128;
+        acc + delta * (delta + 1) / 2
+    })
+}
+
+fn clip(fuel: u128) -> usize {
+    fuel.min(usize::MAX as u128) as usize
+}
+
+// any median minimizes the sum of distances, the lower one is the smallest position
+fn align_linear(xs: &[usize]) -> Alignment {
+    assert!(!xs.is_empty());
+    let mut sorted = xs.to_vec();
+    let mid = (sorted.len() - 1) / 2;
+    let position = *sorted.select_nth_unstable(mid).1;
+    Alignment { position, fuel: distance_a(&position, xs) }
+}
+
+// the triangular cost is (d^2 + d) / 2, its optimum lies within 1/2 of the mean, so within
+// one of the mean rounded down
+fn align_triangular(xs: &[usize]) -> Alignment {
+    assert!(!xs.is_empty());
+    let (min, max) = (*xs.iter().min().unwrap(), *xs.iter().max().unwrap());
+    let mean = (xs.iter().map(|x| *x as u128).sum::<u128>() / xs.len() as u128) as usize;
+    let position = (mean.saturating_sub(1).max(min)..=mean.saturating_add(1).min(max))
+        .min_by_key(|p| (exact_distance_b(p, xs), *p))
+        .unwrap();
+    Alignment { position, fuel: distance_b(&position, xs) }
+}
+
+fn align_convex<C: FuelCost>(xs: &[usize], cost: C) -> Alignment {
+    Crabs::new(xs.to_vec()).align(&cost)
+}
+
+// fuel burned moving one crab the given distance
+trait FuelCost {
+    fn cost(&self, distance: usize) -> usize;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Cost {
+    Linear,      // d
+    Triangular,  // 1 + 2 + .. + d, saturates at usize::MAX
+    Quadratic,   // d^2, saturates at usize::MAX
+    Exponential, // 2^d - 1, saturates at usize::MAX
+}
+
+impl FuelCost for Cost {
+    fn cost(&self, d: usize) -> usize {
+        match self {
+            Cost::Linear => d,
+            Cost::Triangular => match d % 2 {
+                0 => (d / 2).checked_mul(d + 1),
+                _ => d.checked_mul(d / 2 + 1),
+            }
+            .unwrap_or(usize::MAX),
+            Cost::Quadratic => d.saturating_mul(d),
+            Cost::Exponential => 1usize.checked_shl(d as u32).map_or(usize::MAX, |v| v - 1),
+        }
+    }
+}
+
+impl<F: Fn(usize) -> usize> FuelCost for F {
+    fn cost(&self, distance: usize) -> usize {
+        self(distance)
+    }
+}
+
+// positions with a weight per crab, a weight of w counts as w crabs at that position
+#[derive(Debug, Clone)]
+struct Crabs {
+    positions: Vec<usize>,
+    weights: Vec<usize>,
+}
+
+impl Crabs {
+    fn new(positions: Vec<usize>) -> Self {
+        let weights = vec![1; positions.len()];
+        Crabs { positions, weights }
+    }
+
+    fn weighted(positions: Vec<usize>, weights: Vec<usize>) -> Result<Self, String> {
+        if positions.len() != weights.len() {
+            return Err(format!("{} positions but {} weights", positions.len(), weights.len()));
+        }
+        Ok(Crabs { positions, weights })
+    }
+
+    fn range(&self) -> (usize, usize) {
+        assert!(!self.positions.is_empty());
+        let min = *self.positions.iter().min().unwrap();
+        let max = *self.positions.iter().max().unwrap();
+        (min, max)
+    }
+
+    fn fuel<C: FuelCost>(&self, cost: &C, position: usize) -> usize {
+        fuel_at(cost, &self.positions, &self.weights, position)
+    }
+
+    fn align<C: FuelCost>(&self, cost: &C) -> Alignment {
+        let (min, max) = self.range();
+        align_within(cost, &self.positions, &self.weights, min, max)
+    }
+
+    // k meeting points minimizing the total fuel, for costs convex and nondecreasing in the
+    // distance every crab goes to its nearest point, so sorted by position the clusters are
+    // runs and dp[c][j] is the cheapest way to split the first j crabs into c runs
+    fn cluster<C: FuelCost>(&self, cost: &C, k: usize) -> Result<Clustering, String> {
+        let n = self.positions.len();
+        if k == 0 || k > n {
+            return Err(format!("cannot gather {} crabs at {} positions", n, k));
+        }
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by_key(|i| self.positions[*i]);
+        let xs: Vec<usize> = order.iter().map(|i| self.positions[*i]).collect();
+        let ws: Vec<usize> = order.iter().map(|i| self.weights[*i]).collect();
+
+        // best single meeting point for the run i..=j, adding a crab on either end only pulls
+        // it that way, so it lies between the best points of i..j and i+1..=j. that only holds
+        // for exact optima, a run overflowing everywhere has no meaningful best point
+        let mut runs = vec![vec![Alignment { position: 0, fuel: 0 }; n]; n];
+        for len in 0..n {
+            for i in 0..n - len {
+                let j = i + len;
+                let (lo, hi) = match len {
+                    0 => (xs[i], xs[i]),
+                    _ if runs[i][j - 1].fuel == usize::MAX || runs[i + 1][j].fuel == usize::MAX => (xs[i], xs[j]),
+                    _ => (runs[i][j - 1].position, runs[i + 1][j].position),
+                };
+                runs[i][j] = align_within(cost, &xs[i..=j], &ws[i..=j], lo, hi);
+            }
+        }
+
+        let mut dp = vec![vec![usize::MAX; n + 1]; k + 1];
+        let mut split = vec![vec![0; n + 1]; k + 1];
+        dp[0][0] = 0;
+        for c in 1..=k {
+            for j in c..=n {
+                for i in c - 1..j {
+                    if dp[c - 1][i] == usize::MAX {
+                        continue;
+                    }
+                    let fuel = dp[c - 1][i].saturating_add(runs[i][j - 1].fuel);
+                    if fuel < dp[c][j] {
+                        dp[c][j] = fuel;
+                        split[c][j] = i;
+                    }
+                }
+            }
+        }
+
+        let mut centers = vec![0; k];
+        let mut assignment = vec![0; n];
+        let mut j = n;
+        for c in (1..=k).rev() {
+            let i = split[c][j];
+            centers[c - 1] = runs[i][j - 1].position;
+            order[i..j].iter().for_each(|crab| assignment[*crab] = c - 1);
+            j = i;
+        }
+        Ok(Clustering { fuel: dp[k][n], centers, assignment })
+    }
+
+    // total fuel for every position from min to max, exact for costs that are not convex
+    fn curve<C: FuelCost>(&self, cost: &C) -> CostCurve {
+        let (min, max) = self.range();
+        let fuel = (min..=max).map(|p| self.fuel(cost, p)).collect();
+        CostCurve { start: min, fuel }
+    }
+}
+
+// meeting points in increasing order and the index of the point each crab moves to
+#[derive(Debug, Clone, PartialEq)]
+struct Clustering {
+    fuel: usize,
+    centers: Vec<usize>,
+    assignment: Vec<usize>,
+}
+
+fn fuel_at<C: FuelCost>(cost: &C, xs: &[usize], ws: &[usize], position: usize) -> usize {
+    match exact_fuel(cost, xs, ws, position) {
+        Ok(fuel) => fuel.min(usize::MAX as u128) as usize,
+        Err(_) => usize::MAX,
+    }
+}
+
+// the total in u128, which cannot overflow on usize costs and weights, or the index of a
+// crab whose own cost saturated at usize::MAX and so is no longer exact
+fn exact_fuel<C: FuelCost>(cost: &C, xs: &[usize], ws: &[usize], position: usize) -> Result<u128, usize> {
+    xs.iter().zip(ws).enumerate().try_fold(0u128, |acc, (i, (x, w))| {
+        match cost.cost(position.abs_diff(*x)) {
+            c if c == usize::MAX && *w > 0 => Err(i),
+            c => Ok(acc + c as u128 * *w as u128),
+        }
+    })
+}
+
+// any cost convex in the distance makes the total convex in the position, so a binary
+// search for where the total stops decreasing, a discrete ternary search, finds the optimum.
+// a saturated crab cost is taken as infinite, and it only grows moving away from that crab,
+// so the optimum lies on the crab's side
+fn align_within<C: FuelCost>(cost: &C, xs: &[usize], ws: &[usize], mut lo: usize, mut hi: usize) -> Alignment {
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let left = match (exact_fuel(cost, xs, ws, mid), exact_fuel(cost, xs, ws, mid + 1)) {
+            (Ok(a), Ok(b)) => a <= b,
+            (Ok(_), Err(_)) => true,
+            (Err(i), _) => xs[i] <= mid,
+        };
+        if left { hi = mid } else { lo = mid + 1 }
+    }
+    Alignment { position: lo, fuel: fuel_at(cost, xs, ws, lo) }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct CostCurve {
+    start: usize,
+    fuel: Vec<usize>,
+}
+
+impl CostCurve {
+    fn best(&self) -> Alignment {
+        let (i, fuel) = self.fuel.iter().enumerate().min_by_key(|(i, f)| (**f, *i)).unwrap();
+        Alignment { position: self.start + i, fuel: *fuel }
+    }
+
+    // the outermost positions costing at most slack more than the optimum
+    fn within(&self, slack: usize) -> RangeInclusive<usize> {
+        let limit = self.best().fuel.saturating_add(slack);
+        let first = self.fuel.iter().position(|f| *f <= limit).unwrap();
+        let last = self.fuel.iter().rposition(|f| *f <= limit).unwrap();
+        self.start + first..=self.start + last
+    }
 }
 
 fn read_input(input_type: InputType) -> Vec<usize> {
@@ -54,25 +304,242 @@ fn read_input(input_type: InputType) -> Vec<usize> {
         }
     };
 
-    data.split(",")
-        .map(|x| x.parse().unwrap())
+    data.split(',')
+        .map(|x| x.trim().parse().unwrap())
         .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{read_input, part_a, part_b};
-    use utils::InputType;
+    use crate::{
+        align_convex, align_linear, align_triangular, distance_a, distance_b, part_a, part_b, read_input, Alignment, Clustering, Cost,
+        Crabs, FuelCost,
+    };
+    use utils::{InputType, Rng};
+
+    // reference, tries every position from min to max
+    fn brute_force(xs: &[usize], distance: fn(&usize, &[usize]) -> usize) -> Alignment {
+        let max = *xs.iter().max().unwrap();
+        let min = *xs.iter().min().unwrap();
+        (min..=max)
+            .map(|position| Alignment { position, fuel: distance(&position, xs) })
+            .min_by_key(|a| (a.fuel, a.position))
+            .unwrap()
+    }
 
     #[test]
     fn test_part_a() {
         let xs = read_input(InputType::Sample);
         assert_eq!(37, part_a(&xs));
+        assert_eq!(Alignment { position: 2, fuel: 37 }, align_linear(&xs));
     }
 
     #[test]
     fn test_part_b() {
         let xs = read_input(InputType::Sample);
         assert_eq!(168, part_b(&xs));
+        assert_eq!(Alignment { position: 5, fuel: 168 }, align_triangular(&xs));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Rng::new(7);
+        for len in [1, 2, 3, 10, 101] {
+            for spread in [1, 5, 1000] {
+                let xs: Vec<usize> = (0..len).map(|_| rng.below(spread) as usize).collect();
+                assert_eq!(brute_force(&xs, distance_a), align_linear(&xs), "{:?}", xs);
+                assert_eq!(brute_force(&xs, distance_b), align_triangular(&xs), "{:?}", xs);
+                assert_eq!(brute_force(&xs, distance_a), align_convex(&xs, Cost::Linear), "{:?}", xs);
+                assert_eq!(brute_force(&xs, distance_b), align_convex(&xs, |d| d * (d + 1) / 2), "{:?}", xs);
+            }
+        }
+
+        // widely spread positions, brute force would walk billions of positions
+        let xs = vec![0, 1, 2, 1_000_000_000, 2_000_000_000];
+        assert_eq!(Alignment { position: 2, fuel: 2_999_999_999 }, align_linear(&xs));
+        assert_eq!(align_convex(&xs, |d| d), align_linear(&xs));
+        let xs = vec![0, 1, 2_000_000_000];
+        let expected = Alignment { position: 666_666_667, fuel: 1_333_333_334_000_000_000 };
+        assert_eq!(expected, align_triangular(&xs));
+        assert_eq!(align_convex(&xs, |d: usize| d * (d + 1) / 2), align_triangular(&xs));
+
+        // the fuel no longer fits in usize, the position is still exact
+        let xs = vec![0, 0, 0, 10_000_000_000];
+        assert_eq!(Alignment { position: 2_500_000_000, fuel: usize::MAX }, align_triangular(&xs));
+    }
+
+    #[test]
+    fn test_costs() {
+        let costs: Vec<usize> = (0..5).map(|d| Cost::Exponential.cost(d)).collect();
+        assert_eq!(vec![0, 1, 3, 7, 15], costs);
+        assert_eq!(vec![0, 1, 3, 6, 10], (0..5).map(|d| Cost::Triangular.cost(d)).collect::<Vec<_>>());
+        assert_eq!(vec![0, 1, 4, 9, 16], (0..5).map(|d| Cost::Quadratic.cost(d)).collect::<Vec<_>>());
+        assert_eq!(usize::MAX, Cost::Exponential.cost(200));
+    }
+
+    #[test]
+    fn test_curve() {
+        let xs = read_input(InputType::Sample);
+        let crabs = Crabs::new(xs.clone());
+        for cost in [Cost::Linear, Cost::Triangular, Cost::Quadratic, Cost::Exponential] {
+            let curve = crabs.curve(&cost);
+            assert_eq!(17, curve.fuel.len());
+            assert_eq!(curve.best(), crabs.align(&cost), "{:?}", cost);
+        }
+        assert_eq!(37, crabs.curve(&Cost::Linear).best().fuel);
+        assert_eq!(168, crabs.curve(&Cost::Triangular).best().fuel);
+
+        // linear fuel is 37 at position 2 and 41 at positions 1, 3 and 4
+        let curve = crabs.curve(&Cost::Linear);
+        assert_eq!(2..=2, curve.within(0));
+        assert_eq!(2..=3, curve.within(3));
+        assert_eq!(1..=4, curve.within(4));
+        assert_eq!(0..=16, curve.within(usize::MAX));
+
+        // a cost that is not convex, only the curve finds the optimum
+        let step = |d: usize| if d == 0 { 0 } else { 10 };
+        assert_eq!(Alignment { position: 2, fuel: 70 }, crabs.curve(&step).best());
+    }
+
+    #[test]
+    fn test_saturated_costs() {
+        // 2^100 saturates, the optimum at 50 costs 2 * (2^50 - 1)
+        let crabs = Crabs::new(vec![0, 100]);
+        let expected = Alignment { position: 50, fuel: 2251799813685246 };
+        assert_eq!(expected, crabs.align(&Cost::Exponential));
+        assert_eq!(expected, crabs.curve(&Cost::Exponential).best());
+
+        let (mut rng, mut exact) = (Rng::new(145), 0);
+        for _ in 0..20 {
+            let xs: Vec<usize> = (0..6).map(|_| rng.below(120) as usize).collect();
+            let crabs = Crabs::new(xs);
+            let best = crabs.curve(&Cost::Exponential).best();
+            if best.fuel < usize::MAX {
+                assert_eq!(best, crabs.align(&Cost::Exponential), "{:?}", crabs);
+                exact += 1;
+            }
+        }
+        assert!(exact > 10, "{}", exact);
+
+        // every position overflows
+        let crabs = Crabs::new(vec![0, 1000]);
+        assert_eq!(usize::MAX, crabs.align(&Cost::Exponential).fuel);
+
+        // the polynomial costs saturate too, instead of wrapping
+        assert_eq!(usize::MAX, Cost::Quadratic.cost(1 << 32));
+        assert_eq!(usize::MAX, Cost::Triangular.cost(usize::MAX));
+        assert_eq!((1 << 63) + (1 << 31), Cost::Triangular.cost(1 << 32));
+        assert_eq!(usize::MAX, Cost::Triangular.cost(1 << 33));
+
+        // 2^32 apart, the quadratic cost saturates at either end but the middle fits
+        let crabs = Crabs::new(vec![0, 1 << 32]);
+        let expected = Alignment { position: 1 << 31, fuel: 1 << 63 };
+        assert_eq!(expected, crabs.align(&Cost::Quadratic));
+        let crabs = Crabs::new(vec![0, 0, 0, 4_000_000_000]);
+        assert_eq!(align_triangular(&crabs.positions), crabs.align(&Cost::Triangular));
+    }
+
+    #[test]
+    fn test_weights() {
+        assert!(Crabs::weighted(vec![1, 2], vec![1]).is_err());
+
+        // the heavy crab at 10 pulls the median onto itself
+        let crabs = Crabs::weighted(vec![0, 1, 10], vec![1, 1, 3]).unwrap();
+        assert_eq!(Alignment { position: 10, fuel: 19 }, crabs.align(&Cost::Linear));
+        assert_eq!(crabs.curve(&Cost::Linear).best(), crabs.align(&Cost::Linear));
+
+        // integer weights match repeating each crab that many times
+        let mut rng = Rng::new(45);
+        let xs: Vec<usize> = (0..20).map(|_| rng.below(50) as usize).collect();
+        let ws: Vec<usize> = (0..20).map(|_| rng.below(4) as usize).collect();
+        let repeated: Vec<usize> = xs.iter().zip(&ws).flat_map(|(x, w)| vec![*x; *w]).collect();
+        let weighted = Crabs::weighted(xs, ws).unwrap();
+        for cost in [Cost::Linear, Cost::Triangular, Cost::Quadratic] {
+            let expected = Crabs::new(repeated.clone()).align(&cost);
+            assert_eq!(expected.fuel, weighted.align(&cost).fuel, "{:?}", cost);
+        }
     }
-}
\ No newline at end of file
+
+    // every choice of k meeting points, each crab taking the cheapest one
+    fn brute_force_clusters<C: FuelCost>(crabs: &Crabs, cost: &C, k: usize) -> usize {
+        let (min, max) = crabs.range();
+        let mut best = usize::MAX;
+        let mut centers = vec![min; k];
+        loop {
+            let fuel = crabs.positions.iter().zip(&crabs.weights).fold(0usize, |acc, (x, w)| {
+                acc.saturating_add(w.saturating_mul(centers.iter().map(|c| cost.cost(c.abs_diff(*x))).min().unwrap()))
+            });
+            best = best.min(fuel);
+            match centers.iter().position(|c| *c < max) {
+                Some(i) => {
+                    centers[i] += 1;
+                    centers[..i].iter_mut().for_each(|c| *c = min);
+                }
+                None => return best,
+            }
+        }
+    }
+
+    #[test]
+    fn test_cluster() {
+        let crabs = Crabs::new(read_input(InputType::Sample));
+        assert!(crabs.cluster(&Cost::Linear, 0).is_err());
+        assert!(crabs.cluster(&Cost::Linear, 11).is_err());
+
+        // a single meeting point is the plain alignment
+        let one = crabs.cluster(&Cost::Triangular, 1).unwrap();
+        assert_eq!((168, vec![5]), (one.fuel, one.centers));
+        assert_eq!(vec![0; 10], one.assignment);
+
+        // 16 and 14 split off from the crabs near the origin
+        let two = crabs.cluster(&Cost::Linear, 2).unwrap();
+        let expected = Clustering { fuel: 13, centers: vec![2, 14], assignment: vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 1] };
+        assert_eq!(expected, two);
+
+        // one point per crab costs nothing
+        assert_eq!(0, crabs.cluster(&Cost::Quadratic, 10).unwrap().fuel);
+
+        let mut rng = Rng::new(46);
+        for _ in 0..10 {
+            let xs: Vec<usize> = (0..8).map(|_| rng.below(12) as usize).collect();
+            let ws: Vec<usize> = (0..8).map(|_| 1 + rng.below(3) as usize).collect();
+            let crabs = Crabs::weighted(xs, ws).unwrap();
+            for k in 1..=3 {
+                for cost in [Cost::Linear, Cost::Triangular, Cost::Exponential] {
+                    let clusters = crabs.cluster(&cost, k).unwrap();
+                    assert_eq!(brute_force_clusters(&crabs, &cost, k), clusters.fuel, "{:?} {:?} {}", crabs, cost, k);
+                    assert_eq!(clusters.fuel, assigned_fuel(&crabs, &cost, &clusters));
+                }
+            }
+        }
+
+        // spreads past 64 saturate the exponential cost for a single meeting point
+        let crabs = Crabs::new(vec![0, 2, 3, 40, 41, 70, 72, 90]);
+        for k in 1..=3 {
+            for cost in [Cost::Triangular, Cost::Exponential] {
+                let clusters = crabs.cluster(&cost, k).unwrap();
+                assert_eq!(brute_force_clusters(&crabs, &cost, k), clusters.fuel, "{:?} {}", cost, k);
+                assert_eq!(clusters.fuel, assigned_fuel(&crabs, &cost, &clusters));
+            }
+        }
+        let clusters = crabs.cluster(&Cost::Exponential, 3).unwrap();
+        assert_eq!((vec![2, 40, 80], vec![0, 0, 0, 1, 1, 2, 2, 2]), (clusters.centers, clusters.assignment));
+
+        for _ in 0..10 {
+            let xs: Vec<usize> = (0..6).map(|_| rng.below(150) as usize).collect();
+            let ws: Vec<usize> = (0..6).map(|_| 1 + rng.below(3) as usize).collect();
+            let crabs = Crabs::weighted(xs, ws).unwrap();
+            for cost in [Cost::Linear, Cost::Exponential] {
+                let clusters = crabs.cluster(&cost, 2).unwrap();
+                assert_eq!(brute_force_clusters(&crabs, &cost, 2), clusters.fuel, "{:?} {:?}", crabs, cost);
+                assert_eq!(clusters.fuel, assigned_fuel(&crabs, &cost, &clusters));
+            }
+        }
+    }
+
+    fn assigned_fuel<C: FuelCost>(crabs: &Crabs, cost: &C, clusters: &Clustering) -> usize {
+        crabs.positions.iter().zip(&crabs.weights).zip(&clusters.assignment).fold(0usize, |acc, ((x, w), a)| {
+            acc.saturating_add(w.saturating_mul(cost.cost(clusters.centers[*a].abs_diff(*x))))
+        })
+    }
+}
@@ -1,169 +1,315 @@
-use utils::{InputType, from_digits};
-use std::convert::TryInto;
+use utils::{InputType, Rng, glyph_string, glyph_value};
 use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
 
-static SEVEN_ARRAY: &[(&str, u16)] = &[
-    ("abcefg", 0),
-    ("cf", 1),
-    ("acdeg", 2),
-    ("acdfg", 3),
-    ("bcdf", 4),
-    ("abdfg", 5),
-    ("abdefg", 6),
-    ("acf", 7),
-    ("abcdefg", 8),
-    ("abcdfg", 9),
-];
-
 fn main() {
+    // day08 --generate display lines seed, prints only scrambled lines of four outputs, and to
+    // stderr the text of each line and the segment behind each wire in order
+    // day08 display input, also decodes every line of input on a builtin or user defined display
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+    if let ["--generate", display, lines, seed] = args[..] {
+        let display = Display::load(display).unwrap();
+        let mut rng = Rng::new(seed.parse().unwrap());
+        for _ in 0..lines.parse().unwrap() {
+            let s = scramble(&display, 4, &Faults::default(), &mut rng);
+            println!("{}", s.line);
+            eprintln!("{} {}", s.text, display.segments.iter().map(|w| s.wiring[w]).collect::<String>());
+        }
+        return;
+    }
+
     let xs = read_input(InputType::Input);
     println!("Day08 part a = {}", part_a(&xs)); // 514
     println!("Day08 part b = {}", part_b(&xs)); // 1012272
+
+    if let [display, input] = args[..] {
+        let display = Display::load(display).unwrap();
+        let data = std::fs::read_to_string(input).unwrap();
+        for seg in load_input_with(&display, &data).unwrap() {
+            println!("{}", seg.text());
+        }
+    }
 }
 
-fn part_a(xs: &Vec<Segment>) -> usize {
+fn part_a(xs: &[Segment]) -> usize {
     xs.iter().fold(0, |acc, x| acc + x.count_1478())
 }
 
-fn part_b(xs: &Vec<Segment>) -> usize {
-    let m: HashMap<&str, u16> = SEVEN_ARRAY.iter().map(|v| *v).collect();
-    xs.iter()
-        .fold(0, |acc, seg| acc + from_digits(&get_digits(&seg.outputs, &m)))
+fn part_b(xs: &[Segment]) -> usize {
+    xs.iter().fold(0, |acc, seg| acc + seg.value().unwrap())
+}
+
+// the glyphs a display can show and the segments each one lights
+#[derive(Debug, Clone)]
+struct Display {
+    segments: Vec<char>,
+    glyphs: Vec<(char, HashSet<char>)>,
+}
+
+impl Display {
+    // one glyph per line followed by its segments, blank lines and # comments are skipped
+    fn parse(data: &str) -> Result<Display, String> {
+        let mut glyphs: Vec<(char, HashSet<char>)> = Vec::new();
+        for (i, line) in data.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (glyph, segments) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [glyph, segments] => (glyph, segments),
+                _ => return Err(format!("line {}: expected a glyph and its segments", i + 1)),
+            };
+            let mut cs = glyph.chars();
+            let glyph = match (cs.next(), cs.next()) {
+                (Some(c), None) => c,
+                _ => return Err(format!("line {}: glyph {:?} is not a single character", i + 1, glyph)),
+            };
+            let segments: HashSet<char> = segments.chars().collect();
+            if let Some((other, _)) = glyphs.iter().find(|(g, ss)| *g == glyph || *ss == segments) {
+                return Err(format!("line {}: glyph {:?} clashes with glyph {:?}", i + 1, glyph, other));
+            }
+            glyphs.push((glyph, segments));
+        }
+
+        let mut segments: Vec<char> = glyphs.iter().flat_map(|(_, ss)| ss.iter().copied()).collect();
+        segments.sort_unstable();
+        segments.dedup();
+        match segments.len() {
+            0 => Err("no glyphs".to_string()),
+            n if n > 32 => Err(format!("{} segments, at most 32 are supported", n)),
+            _ => Ok(Display { segments, glyphs }),
+        }
+    }
+
+    fn seven() -> Display {
+        Display::parse(include_str!("seven.txt")).unwrap()
+    }
+
+    // a builtin display by name, otherwise a definition file
+    fn load(name: &str) -> Result<Display, String> {
+        match name {
+            "seven" => Ok(Display::seven()),
+            "fourteen" => Display::parse(include_str!("fourteen.txt")),
+            "sixteen" => Display::parse(include_str!("sixteen.txt")),
+            path => Display::parse(&std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?),
+        }
+    }
+
+    fn glyph(&self, segments: &HashSet<char>) -> Option<char> {
+        self.glyphs.iter().find(|(_, ss)| ss == segments).map(|(g, _)| *g)
+    }
 }
 
 #[derive(Debug, Clone)]
 struct Segment {
-    digits: [HashSet<char>; 10],
-    outputs: [Vec<char>; 4],
+    digits: Vec<HashSet<char>>,
+    outputs: Vec<Vec<char>>,
     decoder: HashMap<char, char>,
+    glyphs: Vec<char>,
 }
 
 impl Segment {
-    fn new(digits: [HashSet<char>; 10], outputs: [Vec<char>; 4]) -> Segment {
-        let mut segment = Segment { digits, outputs, decoder: HashMap::new() };
-        segment.load_decoder();
-        segment.decode();
-        segment
+    // digits and outputs may be any subset of the patterns, both constrain the wiring
+    fn new(digits: Vec<HashSet<char>>, outputs: Vec<Vec<char>>) -> Result<Segment, String> {
+        Segment::with_display(&Display::seven(), digits, outputs)
+    }
+
+    fn with_display(display: &Display, digits: Vec<HashSet<char>>, outputs: Vec<Vec<char>>) -> Result<Segment, String> {
+        let mut segment = Segment { digits, outputs, decoder: HashMap::new(), glyphs: Vec::new() };
+        segment.load_decoder(display)?;
+        segment.decode(display);
+        Ok(segment)
+    }
+
+    fn text(&self) -> String {
+        glyph_string(&self.glyphs)
+    }
+
+    fn value(&self) -> Option<usize> {
+        glyph_value(&self.text(), 10)
     }
 
     fn count_1478(&self) -> usize {
         self.outputs
             .iter()
-            .map(|cs| String::from_iter(cs))
+            .map(String::from_iter)
             .fold(0, |acc, s| match s.len() {
                 2 | 3 | 4 | 7 => acc + 1,
                 _ => acc
             })
     }
 
-    fn find_by_len(&self, len: usize) -> Vec<HashSet<char>> {
-        let xs: Vec<HashSet<char>> = self.digits.iter()
-            .filter(|cs| cs.len() == len)
-            .map(|cs| cs.clone())
+    fn load_decoder(&mut self, display: &Display) -> Result<(), String> {
+        let patterns: Vec<HashSet<char>> = self.digits
+            .iter()
+            .cloned()
+            .chain(self.outputs.iter().map(|cs| cs.iter().copied().collect()))
+            .collect();
+
+        match solve_wirings(display, &patterns) {
+            Wirings { first: None, .. } => Err("no solution".to_string()),
+            Wirings { first: Some(wiring), count: 1 } => {
+                self.decoder = wiring;
+                Ok(())
+            }
+            Wirings { count: WIRING_LIMIT, .. } => Err(format!("ambiguous: at least {} wirings", WIRING_LIMIT)),
+            Wirings { count, .. } => Err(format!("ambiguous: {} wirings", count)),
+        }
+    }
+
+    fn decode(&mut self, display: &Display) {
+        for cs in self.outputs.iter_mut() {
+            let mut decoded: Vec<char> = cs
+                .iter()
+                .map(|c| *self.decoder.get(c).unwrap())
+                .collect();
+            decoded.sort_unstable();
+            *cs = decoded;
+        }
+        self.glyphs = self.outputs
+            .iter()
+            .map(|cs| display.glyph(&cs.iter().copied().collect()).unwrap())
             .collect();
-        xs
     }
+}
 
-    fn load_decoder(&mut self) {
-        self.decoder.clear();
+// every wiring, scrambled wire to display segment, under which each pattern lights up a glyph
+// counting stops here, a sparse line on a wide display fits billions of wirings
+const WIRING_LIMIT: usize = 10_000;
 
-        // known numbers
-        let one: HashSet<char> = self.find_by_len(2).first().unwrap().clone();
-        let seven: HashSet<char> = self.find_by_len(3).first().unwrap().clone();
-        let four: HashSet<char> = self.find_by_len(4).first().unwrap().clone();
-        let zero_six_nine: Vec<HashSet<char>> = self.find_by_len(6);
-        let eight: HashSet<char> = self.find_by_len(7).first().unwrap().clone();
+// the first wiring found and how many there are, up to WIRING_LIMIT
+#[derive(Debug, Clone, PartialEq)]
+struct Wirings {
+    first: Option<HashMap<char, char>>,
+    count: usize,
+}
 
-        // find six
-        let six: Vec<HashSet<char>> = zero_six_nine.iter()
-            .filter(|cs| !one.is_subset(cs))
-            .map(|v| v.clone())
-            .collect();
-        let six: HashSet<char> = six.first().unwrap().clone();
+fn solve_wirings(display: &Display, patterns: &[HashSet<char>]) -> Wirings {
+    let wires = &display.segments;
 
-        // find zero and nine
-        let zero_and_nine: Vec<HashSet<char>> = zero_six_nine.iter()
-            .filter(|cs| six.symmetric_difference(cs).collect::<HashSet<&char>>().len() > 0)
-            .map(|cs| cs.clone())
-            .collect();
+    let mask = |cs: Vec<char>| -> Option<u32> {
+        cs.into_iter().try_fold(0, |acc, c| wires.iter().position(|w| *w == c).map(|i| acc | 1 << i))
+    };
+    let glyphs: Vec<u32> = display.glyphs.iter().map(|(_, ss)| mask(ss.iter().copied().collect()).unwrap()).collect();
+    let patterns: Option<Vec<u32>> = patterns.iter().map(|p| mask(p.iter().copied().collect())).collect();
+    let patterns = match patterns {
+        Some(patterns) => patterns,
+        None => return Wirings { first: None, count: 0 }, // a wire the display does not have
+    };
 
-        let zero: Vec<HashSet<char>> = zero_and_nine.iter()
-            .filter(|cs| cs.symmetric_difference(&four).collect::<HashSet<&char>>().len() == 4)
-            .map(|cs| cs.clone())
-            .collect();
-        let zero: HashSet<char> = zero.first().unwrap().clone();
+    let mut search = WiringSearch { glyphs, patterns, wiring: vec![0; wires.len()], used: 0, first: None, count: 0 };
+    search.assign(0);
+    Wirings {
+        first: search.first.map(|wiring| wiring.iter().enumerate().map(|(i, s)| (wires[i], wires[*s])).collect()),
+        count: search.count,
+    }
+}
 
-        let nine: Vec<HashSet<char>> = zero_and_nine.iter()
-            .filter(|cs| **cs != zero)
-            .map(|cs| cs.clone())
-            .collect();
-        let nine: HashSet<char> = nine.first().unwrap().clone();
-
-        // find a
-        let a_encoded: Vec<&char> = seven.symmetric_difference(&one).collect();
-        let a_encoded: char = **a_encoded.first().unwrap();
-        self.decoder.insert(a_encoded, 'a');
-
-        // find f
-        let f_encoded: Vec<&char> = six.intersection(&one).collect();
-        let f_encoded = **f_encoded.first().unwrap();
-        self.decoder.insert(f_encoded, 'f');
-
-        // find c
-        let c_encoded: Vec<&char> = one.iter().filter(|c| **c != f_encoded).collect();
-        let c_encoded = **c_encoded.first().unwrap();
-        self.decoder.insert(c_encoded, 'c');
-
-        // find d
-        let d_encoded: Vec<&char> = eight.symmetric_difference(&zero).collect();
-        let d_encoded = **d_encoded.first().unwrap();
-        self.decoder.insert(d_encoded, 'd');
-
-        // find e
-        let e_encoded: Vec<&char> = eight.symmetric_difference(&nine).collect();
-        let e_encoded = **e_encoded.first().unwrap();
-        self.decoder.insert(e_encoded, 'e');
-
-        // find g
-        let mut temp = four.clone();
-        temp.insert(a_encoded);
-        temp.insert(e_encoded);
-        let g_encoded: Vec<&char> = eight.symmetric_difference(&temp).collect();
-        let g_encoded = **g_encoded.first().unwrap();
-        self.decoder.insert(g_encoded, 'g');
-
-        // find b
-        let mut temp = seven.clone();
-        temp.insert(d_encoded);
-        temp.insert(e_encoded);
-        temp.insert(g_encoded);
-        let b_encoded: Vec<&char> = eight.symmetric_difference(&temp).collect();
-        let b_encoded = **b_encoded.first().unwrap();
-        self.decoder.insert(b_encoded, 'b');
-    }
-
-    fn decode(&mut self) {
-        for i in 0..=3 {
-            let mut cs: Vec<char> = self.outputs[i]
-                .iter()
-                .map(|c| *self.decoder.get(c).unwrap())
-                .collect();
-            cs.sort();
-            self.outputs[i] = cs;
+// backtracking over wire assignments, wire i goes to segment wiring[i]
+struct WiringSearch {
+    glyphs: Vec<u32>,
+    patterns: Vec<u32>,
+    wiring: Vec<usize>,
+    used: u32,
+    first: Option<Vec<usize>>,
+    count: usize,
+}
+
+impl WiringSearch {
+    fn assign(&mut self, wire: usize) {
+        if self.count == WIRING_LIMIT || !self.consistent(wire) {
+            return;
+        }
+        if wire == self.wiring.len() {
+            self.first.get_or_insert_with(|| self.wiring.clone());
+            self.count += 1;
+            return;
+        }
+        for segment in 0..self.wiring.len() {
+            if self.used & 1 << segment == 0 {
+                self.wiring[wire] = segment;
+                self.used |= 1 << segment;
+                self.assign(wire + 1);
+                self.used &= !(1 << segment);
+            }
         }
     }
-}
 
-fn get_digits(css: &[Vec<char>; 4], m: &HashMap<&str, u16>) -> Vec<u16> {
-    css.iter()
-        .map(|cs| {
-            let s: String = cs.iter().collect();
-            *m.get(&*s).unwrap()
+    // with the first n wires placed, every pattern must still fit some glyph of its size:
+    // the glyph lights exactly the placed segments whose wires are in the pattern
+    fn consistent(&self, n: usize) -> bool {
+        self.patterns.iter().all(|pattern| {
+            let lit = (0..n)
+                .filter(|w| pattern & 1 << w != 0)
+                .fold(0, |acc, w| acc | 1 << self.wiring[w]);
+            self.glyphs
+                .iter()
+                .any(|g| g.count_ones() == pattern.count_ones() && g & self.used == lit)
         })
-        .collect()
+    }
 }
 
+// damage applied to a generated line: whole patterns missing from the unique patterns, and
+// patterns with one lit segment dropped or one segment toggled
+#[derive(Debug, Clone, Default)]
+struct Faults {
+    missing: usize,
+    dropped: usize,
+    corrupted: usize,
+}
+
+// a generated line with the wiring, scrambled wire to segment, and the text it shows
+#[derive(Debug, Clone)]
+struct Scrambled {
+    line: String,
+    wiring: HashMap<char, char>,
+    text: String,
+}
+
+fn scramble(display: &Display, outputs: usize, faults: &Faults, rng: &mut Rng) -> Scrambled {
+    let mut wires = display.segments.clone();
+    rng.shuffle(&mut wires);
+    let wiring: HashMap<char, char> = wires.iter().copied().zip(display.segments.iter().copied()).collect();
+    let wire_of: HashMap<char, char> = wiring.iter().map(|(w, s)| (*s, *w)).collect();
+    let render = |rng: &mut Rng, segments: &HashSet<char>| {
+        let mut cs: Vec<char> = segments.iter().map(|s| wire_of[s]).collect();
+        cs.sort_unstable();
+        rng.shuffle(&mut cs);
+        cs
+    };
+
+    let mut patterns: Vec<Vec<char>> = display.glyphs.iter().map(|(_, ss)| render(rng, ss)).collect();
+    rng.shuffle(&mut patterns);
+    patterns.truncate(patterns.len().saturating_sub(faults.missing));
+
+    let glyphs: Vec<char> = (0..outputs)
+        .map(|_| display.glyphs[rng.below(display.glyphs.len() as u64) as usize].0)
+        .collect();
+    let mut shown: Vec<Vec<char>> = glyphs
+        .iter()
+        .map(|g| render(rng, &display.glyphs.iter().find(|(c, _)| c == g).unwrap().1))
+        .collect();
+
+    // faults land anywhere on the line, but never empty a pattern
+    let count = patterns.len() + shown.len();
+    for (n, drop) in [(faults.dropped, true), (faults.corrupted, false)] {
+        for _ in 0..n {
+            let i = rng.below(count as u64) as usize;
+            let cs = if i < patterns.len() { &mut patterns[i] } else { &mut shown[i - patterns.len()] };
+            let wire = if drop { cs[rng.below(cs.len() as u64) as usize] } else { wires[rng.below(wires.len() as u64) as usize] };
+            match cs.iter().position(|c| *c == wire) {
+                Some(j) if cs.len() > 1 => { cs.remove(j); }
+                Some(_) => {}
+                None => cs.push(wire),
+            }
+        }
+    }
+
+    let join = |css: &[Vec<char>]| css.iter().map(|cs| cs.iter().collect::<String>()).collect::<Vec<_>>().join(" ");
+    let line = format!("{} | {}", join(&patterns), join(&shown));
+    Scrambled { line, wiring, text: glyph_string(&glyphs) }
+}
 
 fn read_input(input_type: InputType) -> Vec<Segment> {
     let data = {
@@ -173,42 +319,43 @@ fn read_input(input_type: InputType) -> Vec<Segment> {
         }
     };
 
-    load_input(data)
+    load_input(data).unwrap()
 }
 
-fn load_input(data: &str) -> Vec<Segment> {
-    let ys: Vec<&str> = data
-        .lines()
-        .flat_map(|s| s.split(" | "))
-        .collect();
-
-    let zs: Vec<(&str, &str)> = ys
-        .chunks(2)
-        .map(|ls| (ls[0], ls[1]))
-        .collect();
-
-    let zs: Vec<Segment> = zs.iter()
-        .map(|(s1, s2)| {
-            let ds: Vec<&str> = s1.split(" ").collect();
-            let ds: Vec<HashSet<char>> = ds.iter().map(|s| s.chars().collect()).collect();
-            let digits: [HashSet<char>; 10] = ds.try_into().expect("slice with incorrect length");
+fn load_input(data: &str) -> Result<Vec<Segment>, String> {
+    load_lines(data, Segment::new)
+}
 
-            let os: Vec<&str> = s2.split(" ").collect();
-            let os: Vec<Vec<char>> = os.iter().map(|s| s.chars().collect()).collect();
-            let outputs: [Vec<char>; 4] = os.try_into().expect("slice with incorrect length");
+fn load_input_with(display: &Display, data: &str) -> Result<Vec<Segment>, String> {
+    load_lines(data, |digits, outputs| Segment::with_display(display, digits, outputs))
+}
 
-            Segment::new(digits, outputs)
+fn load_lines<F>(data: &str, segment: F) -> Result<Vec<Segment>, String>
+where
+    F: Fn(Vec<HashSet<char>>, Vec<Vec<char>>) -> Result<Segment, String>,
+{
+    data.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let (s1, s2) = line
+                .split_once(" | ")
+                .ok_or(format!("line {}: missing ' | ' separator", i + 1))?;
+            let digits: Vec<HashSet<char>> = s1.split_whitespace().map(|s| s.chars().collect()).collect();
+            let outputs: Vec<Vec<char>> = s2.split_whitespace().map(|s| s.chars().collect()).collect();
+            segment(digits, outputs).map_err(|e| format!("line {}: {}", i + 1, e))
         })
-        .collect();
-
-    zs
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
-    use utils::{InputType, from_digits};
-    use crate::{load_input, read_input, part_a, SEVEN_ARRAY, get_digits, part_b};
-    use std::collections::HashMap;
+    use utils::{InputType, Rng, glyph_value};
+    use crate::{
+        load_input, load_input_with, read_input, part_a, part_b, scramble, solve_wirings, Display, Faults, Segment, Wirings,
+        WIRING_LIMIT,
+    };
+    use std::collections::{HashMap, HashSet};
 
     #[test]
     fn test_part_a() {
@@ -225,16 +372,185 @@ mod tests {
     #[test]
     fn test_decoder() {
         let data = include_str!("single-sample.txt");
-        let segs = load_input(data);
+        let segs = load_input(data).unwrap();
         for seg in segs {
             println!("decoded outputs: {:?}", seg.outputs);
 
-            let m: HashMap<&str, u16> = SEVEN_ARRAY.iter().map(|v| *v).collect();
-            let digits = get_digits(&seg.outputs, &m);
-            println!("digits: {:?}", digits);
-            let result = from_digits(&digits);
+            let text = seg.text();
+            println!("text: {}", text);
+            let result = glyph_value(&text, 10).unwrap();
             println!("result: {}", result);
             assert_eq!(5353, result)
         }
     }
-}
\ No newline at end of file
+
+    fn sets(s: &str) -> Vec<HashSet<char>> {
+        s.split_whitespace().map(|p| p.chars().collect()).collect()
+    }
+
+    #[test]
+    fn test_partial_patterns() {
+        // the single sample wiring, d->a e->b a->c f->d g->e b->f c->g
+        let expected: HashMap<char, char> = "deafgbc".chars().zip("abcdefg".chars()).collect();
+        let glyphs = Display::seven();
+
+        let all = sets("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab");
+        assert_eq!(Wirings { first: Some(expected.clone()), count: 1 }, solve_wirings(&glyphs, &all));
+
+        // 4, 7 and the three six segment digits pin it down without 1, 8 or the fives
+        let some = sets("eafb dab cefabd cdfgeb cagedb");
+        assert_eq!(Wirings { first: Some(expected), count: 1 }, solve_wirings(&glyphs, &some));
+
+        // 8 lights everything, any of the 5040 wirings fits
+        assert_eq!(5040, solve_wirings(&glyphs, &sets("abcdefg")).count);
+
+        // a lone 1 leaves its two wires, and the other five, free to swap
+        assert_eq!(2 * 120, solve_wirings(&glyphs, &sets("ab")).count);
+    }
+
+    #[test]
+    fn test_decoder_errors() {
+        let err = |digits: &str, outputs: &str| {
+            let outputs = outputs.split_whitespace().map(|p| p.chars().collect()).collect();
+            Segment::new(sets(digits), outputs).unwrap_err()
+        };
+        assert_eq!("ambiguous: 240 wirings", err("ab", "ab"));
+        assert_eq!("no solution", err("ab abcd abcdefgh", ""));
+        assert_eq!("no solution", err("abcdef abcdeg abcdfg abcefg", ""));
+        assert_eq!("no solution", err("ab", "abcde ab abcdefg abz"));
+
+        // outputs alone are enough when they cover the right patterns
+        let outputs = ["eafb", "dab", "cefabd", "cdfgeb", "cagedb"].iter().map(|s| s.chars().collect()).collect();
+        let seg = Segment::new(Vec::new(), outputs).unwrap();
+        assert_eq!(vec!['4', '7', '9', '6', '0'], seg.glyphs);
+
+        assert_eq!("line 2: missing ' | ' separator", load_input("ab | ab abcdefg eafb dab cefabd cdfgeb cagedb\nab ab").unwrap_err());
+        assert_eq!("line 1: ambiguous: 48 wirings", load_input("abc | ab").unwrap_err());
+
+        // 7 is not inside 4
+        assert_eq!("line 1: no solution", load_input("abcd | ab abc").unwrap_err());
+    }
+
+    // renders text on a display whose wires are rotated by shift, every glyph followed by the outputs
+    fn scrambled_line(display: &Display, shift: usize, text: &str) -> String {
+        let n = display.segments.len();
+        let wire = |s: &char| display.segments[(display.segments.iter().position(|x| x == s).unwrap() + shift) % n];
+        let render = |ss: &HashSet<char>| ss.iter().map(wire).collect::<String>();
+        let patterns: Vec<String> = display.glyphs.iter().map(|(_, ss)| render(ss)).collect();
+        let outputs: Vec<String> = text
+            .chars()
+            .map(|c| render(&display.glyphs.iter().find(|(g, _)| *g == c).unwrap().1))
+            .collect();
+        format!("{} | {}", patterns.join(" "), outputs.join(" "))
+    }
+
+    #[test]
+    fn test_displays() {
+        for (name, segments, glyphs) in [("seven", 7, 10), ("fourteen", 14, 36), ("sixteen", 16, 36)] {
+            let display = Display::load(name).unwrap();
+            assert_eq!((segments, glyphs), (display.segments.len(), display.glyphs.len()));
+        }
+
+        let fourteen = Display::load("fourteen").unwrap();
+        let segs = load_input_with(&fourteen, &scrambled_line(&fourteen, 5, "RUST2021")).unwrap();
+        assert_eq!("RUST2021", segs[0].text());
+        assert_eq!(None, segs[0].value());
+
+        let sixteen = Display::load("sixteen").unwrap();
+        let segs = load_input_with(&sixteen, &scrambled_line(&sixteen, 11, "0123456789")).unwrap();
+        assert_eq!(Some(123456789), segs[0].value());
+
+        // a custom three segment display of arrows, wired a->b b->c c->a
+        let arrows = Display::parse("# three bars\n- a\n< ab\n> bc\n^ abc\n").unwrap();
+        let segs = load_input_with(&arrows, "b bc ca | cb cab ca b").unwrap();
+        assert_eq!("<^>-", segs[0].text());
+    }
+
+    #[test]
+    fn test_sparse_wide_line() {
+        // one ten segment pattern on sixteen segments, far too many wirings to count
+        let sixteen = Display::load("sixteen").unwrap();
+        let expected = format!("line 1: ambiguous: at least {} wirings", WIRING_LIMIT);
+        assert_eq!(expected, load_input_with(&sixteen, "abcdefghij | abcdefghij").unwrap_err());
+        assert_eq!(WIRING_LIMIT, solve_wirings(&sixteen, &sets("abcdefghij")).count);
+    }
+
+    #[test]
+    fn test_display_errors() {
+        assert_eq!("no glyphs", Display::parse("# nothing\n").unwrap_err());
+        assert_eq!("line 1: expected a glyph and its segments", Display::parse("1").unwrap_err());
+        assert_eq!("line 2: glyph \"10\" is not a single character", Display::parse("1 ab\n10 abc").unwrap_err());
+        assert_eq!("line 2: glyph '7' clashes with glyph '1'", Display::parse("1 ab\n7 ba").unwrap_err());
+        assert!(Display::load("no-such-display.txt").is_err());
+    }
+
+    #[test]
+    fn test_scramble() {
+        let mut rng = Rng::new(49);
+        let display = Display::seven();
+        let s = scramble(&display, 6, &Faults::default(), &mut rng);
+        let (patterns, outputs) = s.line.split_once(" | ").unwrap();
+        assert_eq!(10, patterns.split(' ').count());
+        assert_eq!(6, outputs.split(' ').count());
+        assert_eq!(6, s.text.len());
+        assert_eq!(7, s.wiring.len());
+
+        let faults = Faults { missing: 4, ..Faults::default() };
+        let s = scramble(&display, 0, &faults, &mut rng);
+        assert_eq!(" | ", &s.line[s.line.len() - 3..]);
+        assert_eq!(6, s.line.split_whitespace().count() - 1);
+    }
+
+    #[test]
+    fn test_fuzz_decoder() {
+        let mut rng = Rng::new(2021);
+        let display = Display::seven();
+
+        // every clean line decodes to its wiring and value
+        for outputs in [1, 4, 9] {
+            for _ in 0..300 {
+                let s = scramble(&display, outputs, &Faults::default(), &mut rng);
+                let seg = &load_input(&s.line).unwrap()[0];
+                assert_eq!(s.wiring, seg.decoder, "{}", s.line);
+                assert_eq!(s.text, seg.text(), "{}", s.line);
+                assert_eq!(glyph_value(&s.text, 10), seg.value());
+            }
+        }
+
+        // missing patterns can leave it ambiguous, but never wrong or unsolvable
+        for missing in 1..=10 {
+            for _ in 0..50 {
+                let s = scramble(&display, 2, &Faults { missing, ..Faults::default() }, &mut rng);
+                match load_input(&s.line) {
+                    Ok(segs) => assert_eq!(s.wiring, segs[0].decoder, "{}", s.line),
+                    Err(e) => assert!(e.starts_with("line 1: ambiguous"), "{}: {}", s.line, e),
+                }
+            }
+        }
+
+        // damaged lines are reported, not panicked on. a lost or extra segment often still
+        // shows a glyph, 8 without a segment is 0, 6 or 9, so some slip through decoded
+        let (mut rejected, mut total) = (0, 0);
+        for (dropped, corrupted) in [(1, 0), (0, 1), (2, 2)] {
+            for _ in 0..100 {
+                let s = scramble(&display, 4, &Faults { dropped, corrupted, ..Faults::default() }, &mut rng);
+                total += 1;
+                if load_input(&s.line).is_err() {
+                    rejected += 1;
+                }
+            }
+        }
+        assert!(rejected * 4 > total * 3, "{} of {} rejected", rejected, total);
+
+        // wider displays
+        for name in ["fourteen", "sixteen"] {
+            let display = Display::load(name).unwrap();
+            for _ in 0..20 {
+                let s = scramble(&display, 8, &Faults::default(), &mut rng);
+                let seg = &load_input_with(&display, &s.line).unwrap()[0];
+                assert_eq!(s.wiring, seg.decoder, "{}", s.line);
+                assert_eq!(s.text, seg.text(), "{}", s.line);
+            }
+        }
+    }
+}
@@ -1,30 +1,75 @@
 use utils::InputType;
-use std::collections::HashSet;
+use std::collections::VecDeque;
 
 fn main() {
     let xss = read_input(InputType::Input);
     println!("Day09 part a = {}", part_a(&xss)); // 591
     println!("Day09 part b = {}", part_b(&xss)); // 1113424
+
+    let basins = label_basins(&xss);
+    let (r, c) = *get_low_spots(&xss).iter().min_by_key(|(r, c)| xss[*r][*c]).unwrap();
+    println!("{} basins, the lowest point ({}, {}) drains {} cells", basins.sizes.len(), r - 1, c - 1, basins.size_at(r, c).unwrap());
 }
 
-fn part_a(xss: &Vec<Vec<u32>>) -> usize {
-    let res = get_low_spots(&xss).iter().fold(0, |acc, (r, c)| acc + 1 + xss[*r][*c] as usize);
-    res
+fn part_a(xss: &[Vec<u32>]) -> usize {
+    get_low_spots(xss).iter().fold(0, |acc, (r, c)| acc + 1 + xss[*r][*c] as usize)
 }
 
-fn part_b(xss: &Vec<Vec<u32>>) -> usize {
-    let low_spots = get_low_spots(&xss);
+fn part_b(xss: &[Vec<u32>]) -> usize {
+    let mut sizes = label_basins(xss).sizes;
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.iter().take(3).product()
+}
 
-    let mut in_basin = HashSet::new();
-    let mut basins: Vec<usize> = low_spots.iter()
-        .map(|(r, c)| count_basin(&mut in_basin, xss, *r, *c))
-        .collect();
+// every cell below 9 labelled with its basin, basins numbered in row major order of their first cell
+#[derive(Debug, Clone)]
+struct Basins {
+    labels: Vec<Vec<Option<usize>>>,
+    sizes: Vec<usize>,
+}
 
-    basins.sort_by(|a, b| b.cmp(a));
-    basins.iter().take(3).fold(1, |acc, v| acc * v)
+impl Basins {
+    fn size_at(&self, r: usize, c: usize) -> Option<usize> {
+        self.labels.get(r)?.get(c)?.map(|label| self.sizes[label])
+    }
 }
 
-fn get_low_spots(m: &Vec<Vec<u32>>) -> Vec<(usize, usize)> {
+// breadth first flood from each unlabelled cell, the queue keeps large basins off the stack
+fn label_basins(m: &[Vec<u32>]) -> Basins {
+    let mut labels: Vec<Vec<Option<usize>>> = m.iter().map(|xs| vec![None; xs.len()]).collect();
+    let mut sizes = Vec::new();
+    let mut queue = VecDeque::new();
+
+    for r in 0..m.len() {
+        for c in 0..m[r].len() {
+            if m[r][c] == 9 || labels[r][c].is_some() {
+                continue;
+            }
+            let label = sizes.len();
+            let mut size = 0;
+            labels[r][c] = Some(label);
+            queue.push_back((r, c));
+            while let Some((r, c)) = queue.pop_front() {
+                size += 1;
+                let neighbors = [(r.wrapping_sub(1), c), (r + 1, c), (r, c.wrapping_sub(1)), (r, c + 1)];
+                for (nr, nc) in neighbors {
+                    match m.get(nr).and_then(|xs| xs.get(nc)) {
+                        Some(v) if *v != 9 && labels[nr][nc].is_none() => {
+                            labels[nr][nc] = Some(label);
+                            queue.push_back((nr, nc));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+    }
+
+    Basins { labels, sizes }
+}
+
+fn get_low_spots(m: &[Vec<u32>]) -> Vec<(usize, usize)> {
     let mut ys = Vec::new();
     let rows = m.len() - 2;
     let cols = m[0].len() - 2;
@@ -38,7 +83,7 @@ fn get_low_spots(m: &Vec<Vec<u32>>) -> Vec<(usize, usize)> {
     ys
 }
 
-fn is_low_spot(m: &Vec<Vec<u32>>, r: usize, c: usize) -> bool {
+fn is_low_spot(m: &[Vec<u32>], r: usize, c: usize) -> bool {
     if r < 1 || r > m.len() - 2 || c < 1 || c > m[0].len() - 2 {
         return false;
     }
@@ -51,33 +96,6 @@ fn is_low_spot(m: &Vec<Vec<u32>>, r: usize, c: usize) -> bool {
         && v < m[r][c + 1]  // right
 }
 
-fn count_basin(in_basin: &mut HashSet<(usize, usize)>, m: &Vec<Vec<u32>>, r: usize, c: usize) -> usize {
-    let mut count = 1;
-    in_basin.insert((r, c));
-
-    if m[r - 1][c] != 9 && !in_basin.contains(&(r - 1, c)) {
-        in_basin.insert((r - 1, c));
-        count += count_basin(in_basin, m, r - 1, c);
-    }
-
-    if m[r + 1][c] != 9 && !in_basin.contains(&(r + 1, c)) {
-        in_basin.insert((r + 1, c));
-        count += count_basin(in_basin, m, r + 1, c);
-    }
-
-    if m[r][c - 1] != 9 && !in_basin.contains(&(r, c - 1)) {
-        in_basin.insert((r, c - 1));
-        count += count_basin(in_basin, m, r, c - 1);
-    }
-
-    if m[r][c + 1] != 9 && !in_basin.contains(&(r, c + 1)) {
-        in_basin.insert((r, c + 1));
-        count += count_basin(in_basin, m, r, c + 1);
-    }
-
-    count
-}
-
 fn read_input(input_type: InputType) -> Vec<Vec<u32>> {
     let data = {
         match input_type {
@@ -102,14 +120,14 @@ fn read_input(input_type: InputType) -> Vec<Vec<u32>> {
     let cols = xss.first().unwrap().len();
     let zeros = vec![9; cols];
     xss.insert(0, zeros.clone());
-    xss.push(zeros.clone());
+    xss.push(zeros);
 
     xss
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{read_input, part_a, part_b};
+    use crate::{read_input, part_a, part_b, label_basins};
     use utils::InputType;
 
     #[test]
@@ -127,4 +145,44 @@ mod tests {
 
         assert_eq!(1134, part_b(&xss));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_label_basins() {
+        let xss = read_input(InputType::Sample);
+        let basins = label_basins(&xss);
+        assert_eq!(vec![3, 9, 14, 9], basins.sizes);
+        assert_eq!(xss.len(), basins.labels.len());
+
+        // padding and 9s are unlabelled, the low points sit in their own basins
+        assert_eq!(None, basins.labels[0][0]);
+        assert_eq!(None, basins.labels[1][3]);
+        assert_eq!(Some(0), basins.labels[1][2]);
+        assert_eq!(Some(1), basins.labels[1][10]);
+        assert_eq!(Some(2), basins.labels[3][3]);
+        assert_eq!(Some(3), basins.labels[5][7]);
+
+        let labelled = basins.labels.iter().flatten().filter(|l| l.is_some()).count();
+        assert_eq!(basins.sizes.iter().sum::<usize>(), labelled);
+    }
+
+    #[test]
+    fn test_large_basin() {
+        // one basin of 360000 cells inside the 9 padding, deep enough to overflow a recursive flood
+        let n = 600;
+        let mut xss = vec![vec![0; n + 2]; n + 2];
+        for xs in xss.iter_mut() {
+            xs[0] = 9;
+            xs[n + 1] = 9;
+        }
+        xss[0] = vec![9; n + 2];
+        xss[n + 1] = vec![9; n + 2];
+        xss[n / 2][n / 2] = 9;
+
+        let basins = label_basins(&xss);
+        assert_eq!(vec![n * n - 1], basins.sizes);
+        assert_eq!(Some(0), basins.labels[n][n]);
+        assert_eq!(Some(n * n - 1), basins.size_at(1, 1));
+        assert_eq!(None, basins.size_at(n / 2, n / 2));
+        assert_eq!(None, basins.size_at(n + 2, 0));
+    }
+}
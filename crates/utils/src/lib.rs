@@ -3,18 +3,40 @@ pub fn flatten_zip3<A, B, C>(v: ((A, B), C)) -> (A, B, C) {
     (a, b, c)
 }
 
-pub fn ones_bit_count(xs: &Vec<u16>, k: usize) -> usize {
-    xs.iter().fold(0 as usize, |acc, x| acc + ((*x as usize & (1 << k)) >> k))
+pub trait Bits: Copy {
+    const WIDTH: usize;
+
+    fn bit(self, k: usize) -> bool;
 }
 
-pub fn from_digits(xs: &Vec<u16>) -> usize {
-    let mut num: usize = 0;
-    let mut fac = 10_usize.pow((xs.len() - 1) as u32);
-    for i in 0..xs.len() {
-        num += fac * *xs.get(i).unwrap() as usize;
-        fac /= 10;
-    }
-    num
+macro_rules! impl_bits {
+    ($($t:ty),*) => {
+        $(impl Bits for $t {
+            const WIDTH: usize = <$t>::BITS as usize;
+
+            fn bit(self, k: usize) -> bool {
+                k < Self::WIDTH && (self >> k) & 1 == 1
+            }
+        })*
+    };
+}
+
+impl_bits!(u8, u16, u32, u64, u128, usize);
+
+pub fn ones_bit_count<T: Bits>(xs: &[T], k: usize) -> usize {
+    xs.iter().filter(|x| x.bit(k)).count()
+}
+
+pub fn glyph_string<T: std::fmt::Display>(xs: &[T]) -> String {
+    xs.iter().map(|x| x.to_string()).collect()
+}
+
+// the number a glyph string shows, None when a glyph is not a digit in the radix
+pub fn glyph_value(s: &str, radix: u32) -> Option<usize> {
+    s.chars().try_fold(0usize, |acc, c| {
+        let digit = c.to_digit(radix)? as usize;
+        acc.checked_mul(radix as usize)?.checked_add(digit)
+    })
 }
 
 pub fn get_neighbors(row: usize, col: usize, rows: usize, cols: usize) -> Option<Vec<(usize, usize)>> {
@@ -41,13 +63,45 @@ pub fn get_neighbors(row: usize, col: usize, rows: usize, cols: usize) -> Option
     Some(xs)
 }
 
+// splitmix64, small and seedable, good enough for simulations and test data
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in 0..n, n must be non zero
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0);
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    pub fn shuffle<T>(&mut self, xs: &mut [T]) {
+        for i in (1..xs.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            xs.swap(i, j);
+        }
+    }
+}
 
 #[derive(Debug)]
 pub enum InputType { Sample, Input }
 
 #[cfg(test)]
 mod tests {
-    use crate::{flatten_zip3, ones_bit_count, from_digits, get_neighbors};
+    use crate::{flatten_zip3, ones_bit_count, glyph_string, glyph_value, get_neighbors, Rng};
 
     #[test]
     fn test_flatten_zip3() {
@@ -57,16 +111,26 @@ mod tests {
 
     #[test]
     fn test_bit_count() {
-        let xs = vec![1, 2, 3, 4];
+        let xs: Vec<u16> = vec![1, 2, 3, 4];
         assert_eq!(2, ones_bit_count(&xs, 0));
         assert_eq!(2, ones_bit_count(&xs, 1));
         assert_eq!(1, ones_bit_count(&xs, 2));
+
+        let ys: Vec<u128> = vec![1 << 127, (1 << 127) | 1, 1];
+        assert_eq!(2, ones_bit_count(&ys, 127));
+        assert_eq!(2, ones_bit_count(&ys, 0));
+        assert_eq!(0, ones_bit_count(&ys, 128));
     }
 
     #[test]
-    fn test_from_digits() {
-        let aaa = from_digits(&vec![5, 3, 5, 3]);
-        assert_eq!(5353, aaa);
+    fn test_glyph_string() {
+        let aaa = glyph_string(&[5, 3, 5, 3]);
+        assert_eq!("5353", aaa);
+        assert_eq!(Some(5353), glyph_value(&aaa, 10));
+        assert_eq!("HELLO", glyph_string(&['H', 'E', 'L', 'L', 'O']));
+        assert_eq!(None, glyph_value("HELLO", 10));
+        assert_eq!(Some(255), glyph_value("Ff", 16));
+        assert_eq!(None, glyph_value("99999999999999999999999", 10));
     }
 
     #[test]
@@ -87,4 +151,20 @@ mod tests {
         assert_eq!(None, get_neighbors(0, 0, 2, 2));
         assert_eq!(None, get_neighbors(0, 5, 5, 5));
     }
+
+    #[test]
+    fn test_rng() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        let xs: Vec<u64> = (0..10).map(|_| a.below(6)).collect();
+        let ys: Vec<u64> = (0..10).map(|_| b.below(6)).collect();
+        assert_eq!(xs, ys);
+        assert!(xs.iter().all(|x| *x < 6));
+
+        let mut zs: Vec<u32> = (0..20).collect();
+        a.shuffle(&mut zs);
+        assert_ne!((0..20).collect::<Vec<u32>>(), zs);
+        zs.sort_unstable();
+        assert_eq!((0..20).collect::<Vec<u32>>(), zs);
+    }
 }